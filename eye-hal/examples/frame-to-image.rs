@@ -31,7 +31,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .expect("Stream is dead")
             .expect("Failed to capture frame");
         let frame = match stream_desc.pixfmt {
-            PixelFormat::Custom(fmt) if fmt == "YUYV" => {
+            PixelFormat::Yuyv(16) => {
                 frame.chunks_exact(4).fold(vec![], |mut acc, v| {
                     // convert form YUYV to RGB
                     let [y, u, _, v]: [u8; 4] = std::convert::TryFrom::try_from(v).unwrap();
@@ -4,7 +4,7 @@ use eye_hal::{PlatformContext, Result};
 
 fn main() -> Result<()> {
     // Create a context
-    let ctx = PlatformContext::all().next().expect("no platform context");
+    let ctx = PlatformContext::default();
 
     // Create a list of valid capture devices in the system.
     let list = ctx.devices()?;
@@ -37,21 +37,30 @@ fn main() -> Result<()> {
                 Type::Bitmask => {
                     println!("      Type    : Bitmask");
                 }
+                Type::Compound => {
+                    println!("      Type    : Compound");
+                }
                 Type::Menu(items) => {
                     println!("      Type    : Menu ==>");
-                    for item in items {
+                    for (index, item) in items {
                         match item {
                             MenuItem::String(str) => {
-                                println!("       - {}", str);
+                                println!("       - {}: {}", index, str);
                             }
                             MenuItem::Number(val) => {
-                                println!("       - {}", val);
+                                println!("       - {}: {}", index, val);
                             }
                         }
                     }
                 }
                 _ => {}
             }
+            if let Some(default) = &ctrl.default {
+                println!("      Default : {:?}", default);
+            }
+            if let Some(value) = &ctrl.value {
+                println!("      Value   : {:?}", value);
+            }
         }
     }
 
@@ -5,7 +5,7 @@ use eye_hal::{PlatformContext, Result};
 
 fn main() -> Result<()> {
     // Create a context
-    let ctx = PlatformContext::all().next().expect("no platform context");
+    let ctx = PlatformContext::default();
 
     // Create a list of valid capture devices in the system.
     let list = ctx.devices()?;
@@ -33,7 +33,7 @@ fn main() -> Result<()> {
                 print!("      {}x{}", res.0, res.1);
                 print!(" : [");
                 for stream in streams {
-                    print!("{:?}, ", stream.interval);
+                    print!("{}, ", stream.interval);
                 }
                 println!("]");
             }
@@ -11,6 +11,19 @@ fn main() -> Result<()> {
         for desc in list {
             println!("{}", desc.uri);
             println!("  product : {}", desc.product);
+            println!("  backend : {}", desc.backend);
+            if let Some(driver) = &desc.driver {
+                println!("  driver  : {}", driver);
+            }
+            if let Some(bus) = &desc.bus {
+                println!("  bus     : {}", bus);
+            }
+            if let Some(usb) = &desc.usb {
+                println!("  usb     : {:04x}:{:04x}", usb.vendor_id, usb.product_id);
+                if let Some(serial) = &usb.serial {
+                    println!("  serial  : {}", serial);
+                }
+            }
         }
     }
 
@@ -5,7 +5,7 @@ use eye_hal::{PlatformContext, Result};
 
 fn main() -> Result<()> {
     // Create a context
-    let ctx = PlatformContext::all().next().expect("no platform context");
+    let ctx = PlatformContext::default();
 
     // Query for available devices.
     let devices = ctx.devices()?;
@@ -24,6 +24,9 @@ fn main() -> Result<()> {
     // Linux for example, most devices support memory-mapped buffers.
     let mut stream = dev.start_stream(&stream_desc)?;
 
+    // The driver may have adjusted the stream (e.g. the frame interval), so print what we got.
+    println!("Format: {:?}", stream.format());
+
     // Here we create a loop and just capture images as long as the device produces them. Normally,
     // this loop will run forever unless we unplug the camera or exit the program.
     let mut megabytes_ps: f64 = 0.0;
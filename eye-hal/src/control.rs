@@ -1,5 +1,18 @@
+use std::collections::VecDeque;
+use std::iter::FusedIterator;
+use std::thread;
+use std::time::{Duration, Instant};
+
 use bitflags::bitflags;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Time between two reads of the controls for backends without change notifications
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Clone)]
 /// Device control
 pub struct Descriptor {
@@ -7,10 +20,16 @@ pub struct Descriptor {
     pub id: u32,
     /// Name
     pub name: String,
+    /// Well-known function of the control, if any
+    pub kind: Option<Kind>,
     /// State type
     pub typ: Type,
     /// State flags
     pub flags: Flags,
+    /// Default value, if reported by the backend
+    pub default: Option<State>,
+    /// Value at the time the controls were queried, if the control is readable
+    pub value: Option<State>,
 }
 
 impl Descriptor {
@@ -23,6 +42,74 @@ impl Descriptor {
     pub fn writable(&self) -> bool {
         self.flags & Flags::WRITE == Flags::WRITE
     }
+
+    /// Returns true if the control currently has an effect on the device
+    pub fn active(&self) -> bool {
+        self.flags & Flags::INACTIVE != Flags::INACTIVE
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+/// Well-known control function
+///
+/// Control IDs are specific to a backend, so portable code should address controls by their kind
+/// instead. The value of a control keeps the type reported by the backend, e.g. auto exposure is a
+/// menu in V4L2 but an on/off switch in OpenPnP.
+///
+/// # Example
+///
+/// ```
+/// use eye_hal::control::{Kind, State};
+/// use eye_hal::platform::{mock, Context};
+/// use eye_hal::traits::{Context as _, Device as _};
+///
+/// let ctx = Context::Mock(mock::Context::default());
+/// let mut dev = ctx.open_device("mock://0").unwrap();
+///
+/// dev.set_control_by_kind(Kind::Brightness, &State::Number(64.0)).unwrap();
+/// assert!(matches!(dev.control_by_kind(Kind::Brightness), Ok(State::Number(v)) if v == 64.0));
+/// assert!(dev.control_by_kind(Kind::Zoom).is_err());
+/// ```
+pub enum Kind {
+    /// Image brightness
+    Brightness,
+    /// Image contrast
+    Contrast,
+    /// Color saturation
+    Saturation,
+    /// Color hue
+    Hue,
+    /// Gamma adjustment
+    Gamma,
+    /// Image sharpness
+    Sharpness,
+    /// Backlight compensation
+    BacklightCompensation,
+    /// Exposure time
+    Exposure,
+    /// Automatic exposure
+    AutoExposure,
+    /// Focus distance
+    Focus,
+    /// Automatic focus
+    AutoFocus,
+    /// White balance (color temperature)
+    WhiteBalance,
+    /// Automatic white balance
+    AutoWhiteBalance,
+    /// Sensor gain
+    Gain,
+    /// Automatic gain
+    AutoGain,
+    /// Optical or digital zoom
+    Zoom,
+    /// Horizontal rotation
+    Pan,
+    /// Vertical rotation
+    Tilt,
+    /// Power line frequency for flicker avoidance
+    PowerLineFrequency,
 }
 
 #[derive(Debug, Clone)]
@@ -43,8 +130,14 @@ pub enum Type {
     String,
     /// Bit field
     Bitmask,
+    /// Array or structure, see [`State::Array`] and [`State::Compound`]
+    Compound,
     /// Menu containing an arbitrary number of items
-    Menu(Vec<MenuItem>),
+    ///
+    /// Each item is listed along with the index used to select it, see [`State::MenuIndex`].
+    /// Indices are not necessarily contiguous, e.g. V4L2 auto exposure only offers 1 and 3 on
+    /// many devices.
+    Menu(Vec<(u32, MenuItem)>),
 }
 
 #[derive(Debug, Clone)]
@@ -66,10 +159,16 @@ bitflags! {
         const READ                  = 0x001;
         /// Value can be written
         const WRITE                 = 0x002;
+        /// Value has no effect at the moment, e.g. manual exposure while auto exposure is on
+        ///
+        /// Inactive controls can still be read and usually written, the value takes effect once
+        /// the control becomes active again.
+        const INACTIVE              = 0x004;
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Device control state
 pub enum State {
     /* Stateless controls */
@@ -79,4 +178,213 @@ pub enum State {
     String(String),
     Boolean(bool),
     Number(f64),
+    /* 64 bit integer controls, which a Number cannot hold exactly, pass their values as Integer64.
+     * All other integers are passed as Number and must not have a fractional part when written. */
+    Integer64(i64),
+    Bitmask(u32),
+
+    /* Menu controls, items are selected by their index */
+    MenuIndex(u32),
+
+    /* Compound controls, arrays hold one value per element, anything else is passed as raw bytes
+     * in native byte order */
+    Array(Vec<i64>),
+    Compound(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+/// Control change event
+pub enum Event {
+    /// The value or the flags of a control changed
+    ControlChanged {
+        /// Control identifier
+        id: u32,
+        /// New value, if the control is readable
+        value: Option<State>,
+        /// New flags, e.g. manual exposure becomes inactive while auto exposure is on
+        flags: Flags,
+    },
+}
+
+/// Receives control change events
+///
+/// Changes are reported for as long as the subscription is alive, no matter whether they were made
+/// through this process, by another application or by the device itself. Backends without change
+/// notifications are polled, so changes which are reverted in between two reads may go unnoticed.
+///
+/// # Example
+///
+/// ```
+/// use eye_hal::control::{Event, Kind, State};
+/// use eye_hal::platform::{mock, Context};
+/// use eye_hal::traits::{Context as _, Device as _};
+///
+/// let ctx = Context::Mock(mock::Context::default());
+/// let mut dev = ctx.open_device("mock://0").unwrap();
+/// let mut subscription = dev.subscribe().unwrap();
+///
+/// dev.set_control_by_kind(Kind::Brightness, &State::Number(64.0)).unwrap();
+/// match subscription.next() {
+///     Some(Ok(Event::ControlChanged { value, .. })) => {
+///         assert_eq!(value, Some(State::Number(64.0)))
+///     }
+///     _ => panic!("expected a ControlChanged event"),
+/// }
+/// ```
+pub struct Subscription<'a> {
+    source: Box<dyn FnMut(Duration) -> Option<Result<Event>> + 'a>,
+    /// The source returned `None`, so no more events can arrive
+    done: bool,
+}
+
+impl<'a> Subscription<'a> {
+    /// Returns a subscription fed by an event source
+    ///
+    /// This is meant to be used by backends: the source waits no longer than the given timeout
+    /// for the next event and returns `None` once no more events can arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Event source
+    pub fn new<F>(source: F) -> Self
+    where
+        F: FnMut(Duration) -> Option<Result<Event>> + 'a,
+    {
+        Subscription {
+            source: Box::new(source),
+            done: false,
+        }
+    }
+
+    /// Returns a subscription which detects changes by reading the controls periodically
+    ///
+    /// This is meant to be used by backends which do not notify about changes on their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `controls` - Returns all controls along with their current values
+    pub fn poll<F>(mut controls: F) -> Result<Self>
+    where
+        F: FnMut() -> Result<Vec<Descriptor>> + 'a,
+    {
+        // Only changes are reported, so remember the current state.
+        let mut known = controls()?;
+        let mut pending = VecDeque::new();
+
+        Ok(Subscription::new(move |timeout| {
+            let deadline = Instant::now().checked_add(timeout);
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some(Ok(event));
+                }
+
+                let current = match controls() {
+                    Ok(current) => current,
+                    Err(e) => return Some(Err(e)),
+                };
+                pending.extend(changes(&known, &current));
+                known = current;
+                if !pending.is_empty() {
+                    continue;
+                }
+
+                let now = Instant::now();
+                match deadline {
+                    Some(deadline) if now >= deadline => {
+                        return Some(Err(Error::new(ErrorKind::Timeout, "no event available")))
+                    }
+                    Some(deadline) => thread::sleep(POLL_INTERVAL.min(deadline - now)),
+                    None => thread::sleep(POLL_INTERVAL),
+                }
+            }
+        }))
+    }
+
+    /// Waits for the next event, but no longer than `timeout`
+    ///
+    /// Returns `None` once no more events can arrive, e.g. because the device is gone. If no
+    /// event arrived in time, an error of kind [`ErrorKind::Timeout`] is returned.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<Result<Event>> {
+        if self.done {
+            return None;
+        }
+
+        let item = (self.source)(timeout);
+        self.done = item.is_none();
+        item
+    }
+
+    /// Returns the next event without blocking
+    ///
+    /// If no event is pending, an error of kind [`ErrorKind::Timeout`] is returned.
+    pub fn try_next(&mut self) -> Option<Result<Event>> {
+        self.next_timeout(Duration::ZERO)
+    }
+}
+
+impl<'a> Iterator for Subscription<'a> {
+    type Item = Result<Event>;
+
+    /// Blocks until the next event arrives, returns `None` once no more events can arrive
+    ///
+    /// Errors do not end the subscription, e.g. a failed read of the controls is returned and the
+    /// next call keeps waiting for events.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_timeout(POLL_INTERVAL)? {
+                Err(e) if e.kind() == ErrorKind::Timeout => continue,
+                item => return Some(item),
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for Subscription<'a> {}
+
+/// Returns events for all controls whose value or flags differ between two snapshots
+fn changes(old: &[Descriptor], new: &[Descriptor]) -> Vec<Event> {
+    new.iter()
+        .filter(|desc| {
+            !old.iter().any(|prev| {
+                prev.id == desc.id && prev.value == desc.value && prev.flags == desc.flags
+            })
+        })
+        .map(|desc| Event::ControlChanged {
+            id: desc.id,
+            value: desc.value.clone(),
+            flags: desc.flags,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscription_continues_after_errors() {
+        let mut items = vec![
+            Some(Err(Error::new(ErrorKind::Timeout, "no event available"))),
+            Some(Err(Error::new(ErrorKind::Io, "interrupted"))),
+            Some(Ok(Event::ControlChanged {
+                id: 1,
+                value: None,
+                flags: Flags::NONE,
+            })),
+            None,
+            Some(Err(Error::new(ErrorKind::Other, "after the end"))),
+        ]
+        .into_iter();
+        let mut subscription = Subscription::new(move |_| items.next().unwrap());
+
+        assert!(matches!(subscription.next(), Some(Err(e)) if e.kind() == ErrorKind::Io));
+        assert!(matches!(
+            subscription.next(),
+            Some(Ok(Event::ControlChanged { id: 1, .. }))
+        ));
+        // fused, the source is not called again once it ended
+        assert!(subscription.next().is_none());
+        assert!(subscription.next().is_none());
+    }
 }
@@ -1,8 +1,140 @@
-#[derive(Clone, Debug)]
+use std::sync::{mpsc, Arc, Weak};
+use std::time::Duration;
+
+use crate::error::{Error, ErrorKind, Result};
+
+#[derive(Clone, Debug, Default)]
 /// Device description
 pub struct Description {
     /// Unique resource identifier
     pub uri: String,
     /// Human-readable product name
     pub product: String,
+    /// Name of the HAL which produced this description, e.g. "v4l2"
+    pub backend: String,
+    /// Name of the kernel driver, if known
+    pub driver: Option<String>,
+    /// Location of the device on its bus, e.g. "usb-0000:00:14.0-1"
+    pub bus: Option<String>,
+    /// USB device information, only available for USB devices
+    pub usb: Option<Usb>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// USB device information
+///
+/// The serial number is what tells apart two cameras of the same model. Not all devices have one
+/// though, in which case the bus number and address identify the port the device is plugged into
+/// for as long as it stays connected.
+pub struct Usb {
+    /// Vendor ID (VID)
+    pub vendor_id: u16,
+    /// Product ID (PID)
+    pub product_id: u16,
+    /// Serial number, if the device reports one
+    pub serial: Option<String>,
+    /// Number of the bus the device is connected to
+    pub bus_number: u8,
+    /// Address of the device on its bus
+    pub address: u8,
+}
+
+#[derive(Clone, Debug)]
+/// Hotplug event
+pub enum Event {
+    /// A device became available
+    Added(Description),
+    /// A device is no longer available, identified by its URI
+    Removed(String),
+}
+
+/// Receives hotplug events
+///
+/// Events are reported for as long as the watcher is alive. Dropping it stops the monitoring.
+///
+/// # Example
+///
+/// ```
+/// use eye_hal::device::Event;
+/// use eye_hal::platform::mock;
+/// use eye_hal::traits::Context;
+///
+/// let mut ctx = mock::Context::new();
+/// let mut watcher = ctx.watch().unwrap();
+///
+/// let uri = ctx.plug(mock::Config::default());
+/// match watcher.next() {
+///     Some(Event::Added(desc)) => assert_eq!(desc.uri, uri),
+///     _ => panic!("expected an Added event"),
+/// }
+/// ```
+pub struct Watcher {
+    rx: mpsc::Receiver<Event>,
+    _alive: Arc<()>,
+}
+
+impl Watcher {
+    /// Returns a watcher along with the notifier which feeds it
+    ///
+    /// This is meant to be used by backends: the notifier is handed to whatever monitors the
+    /// devices, e.g. a background thread.
+    pub fn new() -> (Self, Notifier) {
+        let (tx, rx) = mpsc::channel();
+        let alive = Arc::new(());
+        let notifier = Notifier {
+            tx,
+            alive: Arc::downgrade(&alive),
+        };
+
+        (Watcher { rx, _alive: alive }, notifier)
+    }
+
+    /// Waits for the next event, but no longer than `timeout`
+    ///
+    /// Returns `None` once the monitoring has ended. If no event arrived in time, an error of kind
+    /// [`ErrorKind::Timeout`] is returned.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<Result<Event>> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(event) => Some(Ok(event)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                Some(Err(Error::new(ErrorKind::Timeout, "no event available")))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Returns the next event without blocking
+    ///
+    /// If no event is pending, an error of kind [`ErrorKind::Timeout`] is returned.
+    pub fn try_next(&mut self) -> Option<Result<Event>> {
+        self.next_timeout(Duration::ZERO)
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = Event;
+
+    /// Blocks until the next event arrives, returns `None` once the monitoring has ended
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rx.recv().ok()
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Sends hotplug events to a [`Watcher`]
+pub struct Notifier {
+    tx: mpsc::Sender<Event>,
+    alive: Weak<()>,
+}
+
+impl Notifier {
+    /// Sends an event, returns false if the watcher is gone
+    pub fn notify(&self, event: Event) -> bool {
+        self.tx.send(event).is_ok()
+    }
+
+    /// Returns true if the watcher was dropped
+    pub fn is_closed(&self) -> bool {
+        self.alive.strong_count() == 0
+    }
 }
@@ -29,14 +29,31 @@ impl fmt::Debug for Repr {
 
 #[derive(Debug)]
 struct Custom {
-    _kind: ErrorKind,
+    kind: ErrorKind,
     error: Box<dyn error::Error + Send + Sync>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// This operation is not supported.
     NotSupported,
+    /// The operation did not complete in time.
+    Timeout,
+    /// The device is in use, e.g. streaming to another process.
+    Busy,
+    /// The device is gone, e.g. because it was unplugged.
+    Disconnected,
+    /// The caller lacks the permissions to access the device.
+    PermissionDenied,
+    /// A parameter (such as a URI or control value) was rejected.
+    InvalidArgument,
+    /// The pixel format is not supported.
+    UnsupportedFormat,
+    /// The device or resource does not exist.
+    NotFound,
+    /// An I/O error not part of this list.
+    Io,
     /// Any other error not part of this list.
     Other,
 }
@@ -45,11 +62,57 @@ impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ErrorKind::NotSupported => write!(f, "not supported"),
+            ErrorKind::Timeout => write!(f, "timed out"),
+            ErrorKind::Busy => write!(f, "device or resource busy"),
+            ErrorKind::Disconnected => write!(f, "device disconnected"),
+            ErrorKind::PermissionDenied => write!(f, "permission denied"),
+            ErrorKind::InvalidArgument => write!(f, "invalid argument"),
+            ErrorKind::UnsupportedFormat => write!(f, "unsupported format"),
+            ErrorKind::NotFound => write!(f, "not found"),
+            ErrorKind::Io => write!(f, "I/O error"),
             ErrorKind::Other => write!(f, "other"),
         }
     }
 }
 
+impl From<&io::Error> for ErrorKind {
+    fn from(error: &io::Error) -> Self {
+        #[cfg(target_os = "linux")]
+        if let Some(kind) = error.raw_os_error().and_then(errno) {
+            return kind;
+        }
+
+        match error.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            io::ErrorKind::InvalidInput => ErrorKind::InvalidArgument,
+            io::ErrorKind::Unsupported => ErrorKind::NotSupported,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+/// Maps the error numbers returned by V4L2 ioctls
+///
+/// See <https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/gen-errors.html>.
+fn errno(errno: i32) -> Option<ErrorKind> {
+    match errno {
+        libc::EBUSY => Some(ErrorKind::Busy),
+        // ENODEV is returned by ioctls on nodes whose device was unplugged
+        libc::ENODEV | libc::ENXIO => Some(ErrorKind::Disconnected),
+        libc::EACCES | libc::EPERM => Some(ErrorKind::PermissionDenied),
+        libc::EINVAL | libc::ERANGE => Some(ErrorKind::InvalidArgument),
+        libc::ETIMEDOUT => Some(ErrorKind::Timeout),
+        libc::ENOENT => Some(ErrorKind::NotFound),
+        // the ioctl is not implemented by the driver
+        libc::ENOTTY => Some(ErrorKind::NotSupported),
+        libc::EIO => Some(ErrorKind::Io),
+        _ => None,
+    }
+}
+
 impl Error {
     pub fn new<E>(kind: ErrorKind, error: E) -> Self
     where
@@ -57,11 +120,37 @@ impl Error {
     {
         Error {
             repr: Repr::Custom(Box::new(Custom {
-                _kind: kind,
+                kind,
                 error: error.into(),
             })),
         }
     }
+
+    /// Returns the corresponding error kind
+    pub fn kind(&self) -> ErrorKind {
+        match &self.repr {
+            Repr::Simple(kind) => *kind,
+            Repr::Custom(c) => c.kind,
+        }
+    }
+
+    /// Returns a reference to the wrapped error, e.g. the [`io::Error`] reported by the OS
+    ///
+    /// Errors created from an [`ErrorKind`] alone do not wrap anything.
+    pub fn get_ref(&self) -> Option<&(dyn error::Error + Send + Sync + 'static)> {
+        match &self.repr {
+            Repr::Simple(_) => None,
+            Repr::Custom(c) => Some(&*c.error),
+        }
+    }
+
+    /// Consumes the error, returning the wrapped error (if any)
+    pub fn into_inner(self) -> Option<Box<dyn error::Error + Send + Sync>> {
+        match self.repr {
+            Repr::Simple(_) => None,
+            Repr::Custom(c) => Some(c.error),
+        }
+    }
 }
 
 impl From<ErrorKind> for Error {
@@ -74,9 +163,11 @@ impl From<ErrorKind> for Error {
 
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Self {
+        let kind = ErrorKind::from(&error);
+
         Error {
             repr: Repr::Custom(Box::new(Custom {
-                _kind: ErrorKind::Other,
+                kind,
                 error: error.into(),
             })),
         }
@@ -93,7 +184,49 @@ impl fmt::Display for Error {
 }
 
 impl error::Error for Error {
-    fn cause(&self) -> Option<&dyn std::error::Error> {
-        None
+    /// Returns the source of the wrapped error, if any
+    ///
+    /// Like [`io::Error`], the wrapped error itself is not reported as the source since its message
+    /// is what this error displays already. Use [`Error::get_ref`] to access it.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.repr {
+            Repr::Simple(_) => None,
+            Repr::Custom(c) => c.error.source(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::error::Error as _;
+
+    #[test]
+    fn message_is_not_its_own_source() {
+        let error = Error::new(ErrorKind::InvalidArgument, "invalid URI");
+        assert_eq!(error.to_string(), "invalid URI");
+        assert!(error.source().is_none());
+        assert_eq!(error.get_ref().unwrap().to_string(), "invalid URI");
+    }
+
+    #[test]
+    fn wrapped_io_error() {
+        let error = Error::from(io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+
+        let inner = error.into_inner().unwrap();
+        assert_eq!(
+            inner.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn simple_error() {
+        let error = Error::from(ErrorKind::Timeout);
+        assert_eq!(error.to_string(), "timed out");
+        assert!(error.get_ref().is_none());
+        assert!(error.into_inner().is_none());
     }
 }
@@ -1,12 +1,30 @@
 use std::cmp::{Eq, PartialEq};
+use std::convert::TryFrom;
 use std::fmt;
 use std::hash::Hash;
+use std::str::FromStr;
+
+use crate::error::{Error, ErrorKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Arrangement of the color filters of a Bayer sensor, named after the first two rows
+pub enum BayerPattern {
+    /// Blue, Green / Green, Red
+    Bggr,
+    /// Green, Blue / Red, Green
+    Gbrg,
+    /// Green, Red / Blue, Green
+    Grbg,
+    /// Red, Green / Green, Blue
+    Rggb,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 /// Pixel format type used to describe image pixels.
 ///
 /// Arbitrary formats can be wrapped in the Custom variant.
-/// The other variants have values describing the depth of a whole pixel in bits.
+/// The other variants have values describing the depth of a whole pixel in bits. For formats with
+/// subsampled chroma, this is the average depth, e.g. 12 bits for NV12.
 pub enum PixelFormat {
     /// Special type for application defined formats
     Custom(String),
@@ -20,9 +38,49 @@ pub enum PixelFormat {
     Bgr(u32),
     /// Red, Green, Blue
     Rgb(u32),
+    /// Blue, Green, Red, Alpha
+    Bgra(u32),
+    /// Red, Green, Blue, Alpha
+    Rgba(u32),
+    /// Red, Green, Blue packed into a little endian 16 bit word (5, 6 and 5 bits)
+    Rgb565,
+
+    /// Raw sensor data of a Bayer color filter array, one component per pixel
+    Bayer(BayerPattern, u32),
+
+    /// Packed YUV 4:2:2 (Y0, U, Y1, V)
+    Yuyv(u32),
+    /// Packed YUV 4:2:2 (U, Y0, V, Y1)
+    Uyvy(u32),
+    /// Packed YUV 4:2:2 (Y0, V, Y1, U)
+    Yvyu(u32),
+    /// Packed YUV 4:2:2 (V, Y0, U, Y1)
+    Vyuy(u32),
+    /// Semi-planar YUV 4:2:0 (Y plane, interleaved U/V plane)
+    Nv12(u32),
+    /// Semi-planar YUV 4:2:0 (Y plane, interleaved V/U plane)
+    Nv21(u32),
+    /// Semi-planar YUV 4:2:2 (Y plane, interleaved U/V plane)
+    Nv16(u32),
+    /// Semi-planar YUV 4:2:2 (Y plane, interleaved V/U plane)
+    Nv61(u32),
+    /// Planar YUV 4:2:0 (Y, U and V planes)
+    I420(u32),
+    /// Planar YUV 4:2:0 (Y, V and U planes)
+    Yv12(u32),
+    /// Planar YUV 4:2:2 (Y, U and V planes)
+    I422(u32),
+    /// Planar YUV 4:4:4 (Y, U and V planes)
+    I444(u32),
+    /// Packed YUV 4:4:4 (U, Y, V)
+    Uyv(u32),
 
     /// JPEG compression
     Jpeg,
+    /// H.264 (AVC) compression
+    H264,
+    /// H.265 (HEVC) compression
+    Hevc,
 }
 
 impl PixelFormat {
@@ -36,10 +94,37 @@ impl PixelFormat {
             PixelFormat::Gray(bits) => Some(*bits),
             PixelFormat::Bgr(bits) => Some(*bits),
             PixelFormat::Rgb(bits) => Some(*bits),
+            PixelFormat::Bgra(bits) => Some(*bits),
+            PixelFormat::Rgba(bits) => Some(*bits),
+            PixelFormat::Rgb565 => Some(16),
+            PixelFormat::Bayer(_, bits) => Some(*bits),
+            PixelFormat::Yuyv(bits) => Some(*bits),
+            PixelFormat::Uyvy(bits) => Some(*bits),
+            PixelFormat::Yvyu(bits) => Some(*bits),
+            PixelFormat::Vyuy(bits) => Some(*bits),
+            PixelFormat::Nv12(bits) => Some(*bits),
+            PixelFormat::Nv21(bits) => Some(*bits),
+            PixelFormat::Nv16(bits) => Some(*bits),
+            PixelFormat::Nv61(bits) => Some(*bits),
+            PixelFormat::I420(bits) => Some(*bits),
+            PixelFormat::Yv12(bits) => Some(*bits),
+            PixelFormat::I422(bits) => Some(*bits),
+            PixelFormat::I444(bits) => Some(*bits),
+            PixelFormat::Uyv(bits) => Some(*bits),
             // Compressed
             PixelFormat::Jpeg => None,
+            PixelFormat::H264 => None,
+            PixelFormat::Hevc => None,
         }
     }
+
+    /// Returns true for compressed formats, whose frames vary in size
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self,
+            PixelFormat::Jpeg | PixelFormat::H264 | PixelFormat::Hevc
+        )
+    }
 }
 
 impl fmt::Display for PixelFormat {
@@ -48,7 +133,230 @@ impl fmt::Display for PixelFormat {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Four character code identifying a pixel format
+///
+/// Codes of less than four characters are padded with spaces, e.g. "Y16 " as defined by V4L2.
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use eye_hal::format::{FourCC, PixelFormat};
+///
+/// let fourcc: FourCC = "Y16".parse().unwrap();
+/// assert_eq!(fourcc, FourCC::new(b"Y16 "));
+/// assert_eq!(PixelFormat::from(fourcc), PixelFormat::Gray(16));
+///
+/// // aliases of other platforms map to the same pixel format
+/// let yuy2 = FourCC::new(b"YUY2");
+/// assert_eq!(PixelFormat::from(yuy2), PixelFormat::Yuyv(16));
+/// assert_eq!(FourCC::try_from(&PixelFormat::Yuyv(16)).unwrap().to_string(), "YUYV");
+/// ```
+pub struct FourCC {
+    /// Characters as raw bytes
+    pub repr: [u8; 4],
+}
+
+impl FourCC {
+    /// Returns a four character code
+    ///
+    /// # Arguments
+    ///
+    /// * `repr` - Four characters as raw bytes
+    pub const fn new(repr: &[u8; 4]) -> Self {
+        FourCC { repr: *repr }
+    }
+}
+
+impl fmt::Display for FourCC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // omit the padding, so codes can be used as words in text formats
+        write!(f, "{}", String::from_utf8_lossy(&self.repr).trim_end())
+    }
+}
+
+impl FromStr for FourCC {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > 4 || !s.bytes().all(|b| b.is_ascii_graphic() || b == b' ') {
+            return Err(Error::new(
+                ErrorKind::InvalidArgument,
+                format!("invalid four character code: {:?}", s),
+            ));
+        }
+
+        let mut repr = [b' '; 4];
+        repr[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(FourCC { repr })
+    }
+}
+
+/// Mapping of four character codes to pixel formats, shared by all backends
+///
+/// Codes are the ones defined by V4L2, see
+/// <https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/pixfmt.html>, followed by
+/// aliases reported by other platforms (Media Foundation, AVFoundation). The first code of a pixel
+/// format is the one it maps back to.
+const FOURCCS: &[(FourCC, PixelFormat)] = &[
+    // Mono (single-component) formats
+    (FourCC::new(b"GREY"), PixelFormat::Gray(8)),
+    (FourCC::new(b"Y10 "), PixelFormat::Gray(10)),
+    (FourCC::new(b"Y12 "), PixelFormat::Gray(12)),
+    (FourCC::new(b"Y16 "), PixelFormat::Gray(16)),
+    (FourCC::new(b"Z16 "), PixelFormat::Depth(16)),
+    // RGB formats
+    (FourCC::new(b"BGR3"), PixelFormat::Bgr(24)),
+    (FourCC::new(b"RGB3"), PixelFormat::Rgb(24)),
+    (FourCC::new(b"XR24"), PixelFormat::Bgr(32)),
+    (FourCC::new(b"XB24"), PixelFormat::Rgb(32)),
+    (FourCC::new(b"AR24"), PixelFormat::Bgra(32)),
+    (FourCC::new(b"AB24"), PixelFormat::Rgba(32)),
+    (FourCC::new(b"RGBP"), PixelFormat::Rgb565),
+    // Bayer formats, samples of more than 8 bits are stored in 16 bit words
+    (
+        FourCC::new(b"BA81"),
+        PixelFormat::Bayer(BayerPattern::Bggr, 8),
+    ),
+    (
+        FourCC::new(b"GBRG"),
+        PixelFormat::Bayer(BayerPattern::Gbrg, 8),
+    ),
+    (
+        FourCC::new(b"GRBG"),
+        PixelFormat::Bayer(BayerPattern::Grbg, 8),
+    ),
+    (
+        FourCC::new(b"RGGB"),
+        PixelFormat::Bayer(BayerPattern::Rggb, 8),
+    ),
+    (
+        FourCC::new(b"BG10"),
+        PixelFormat::Bayer(BayerPattern::Bggr, 10),
+    ),
+    (
+        FourCC::new(b"GB10"),
+        PixelFormat::Bayer(BayerPattern::Gbrg, 10),
+    ),
+    (
+        FourCC::new(b"BA10"),
+        PixelFormat::Bayer(BayerPattern::Grbg, 10),
+    ),
+    (
+        FourCC::new(b"RG10"),
+        PixelFormat::Bayer(BayerPattern::Rggb, 10),
+    ),
+    (
+        FourCC::new(b"BG12"),
+        PixelFormat::Bayer(BayerPattern::Bggr, 12),
+    ),
+    (
+        FourCC::new(b"GB12"),
+        PixelFormat::Bayer(BayerPattern::Gbrg, 12),
+    ),
+    (
+        FourCC::new(b"BA12"),
+        PixelFormat::Bayer(BayerPattern::Grbg, 12),
+    ),
+    (
+        FourCC::new(b"RG12"),
+        PixelFormat::Bayer(BayerPattern::Rggb, 12),
+    ),
+    (
+        FourCC::new(b"BYR2"),
+        PixelFormat::Bayer(BayerPattern::Bggr, 16),
+    ),
+    (
+        FourCC::new(b"GB16"),
+        PixelFormat::Bayer(BayerPattern::Gbrg, 16),
+    ),
+    (
+        FourCC::new(b"GR16"),
+        PixelFormat::Bayer(BayerPattern::Grbg, 16),
+    ),
+    (
+        FourCC::new(b"RG16"),
+        PixelFormat::Bayer(BayerPattern::Rggb, 16),
+    ),
+    // YUV formats
+    (FourCC::new(b"YUYV"), PixelFormat::Yuyv(16)),
+    (FourCC::new(b"UYVY"), PixelFormat::Uyvy(16)),
+    (FourCC::new(b"YVYU"), PixelFormat::Yvyu(16)),
+    (FourCC::new(b"VYUY"), PixelFormat::Vyuy(16)),
+    (FourCC::new(b"NV12"), PixelFormat::Nv12(12)),
+    (FourCC::new(b"NV21"), PixelFormat::Nv21(12)),
+    (FourCC::new(b"NV16"), PixelFormat::Nv16(16)),
+    (FourCC::new(b"NV61"), PixelFormat::Nv61(16)),
+    (FourCC::new(b"YU12"), PixelFormat::I420(12)),
+    (FourCC::new(b"YV12"), PixelFormat::Yv12(12)),
+    (FourCC::new(b"422P"), PixelFormat::I422(16)),
+    // I444 has no code: YM24 (YUV444M) stores its planes in separate buffers, which the
+    // single-planar V4L2 API cannot negotiate
+    // Compressed formats
+    (FourCC::new(b"MJPG"), PixelFormat::Jpeg),
+    (FourCC::new(b"H264"), PixelFormat::H264),
+    (FourCC::new(b"HEVC"), PixelFormat::Hevc),
+    // Aliases
+    (FourCC::new(b"Y800"), PixelFormat::Gray(8)),
+    (FourCC::new(b"YUY2"), PixelFormat::Yuyv(16)),
+    (FourCC::new(b"yuvs"), PixelFormat::Yuyv(16)),
+    (FourCC::new(b"2vuy"), PixelFormat::Uyvy(16)),
+    (FourCC::new(b"420v"), PixelFormat::Nv12(12)),
+    (FourCC::new(b"420f"), PixelFormat::Nv12(12)),
+    (FourCC::new(b"I420"), PixelFormat::I420(12)),
+    (FourCC::new(b"IYUV"), PixelFormat::I420(12)),
+    (FourCC::new(b"IYU2"), PixelFormat::Uyv(24)),
+    (FourCC::new(b"JPEG"), PixelFormat::Jpeg),
+    (FourCC::new(b"jpeg"), PixelFormat::Jpeg),
+    (FourCC::new(b"dmb1"), PixelFormat::Jpeg),
+    (FourCC::new(b"avc1"), PixelFormat::H264),
+    (FourCC::new(b"hvc1"), PixelFormat::Hevc),
+];
+
+impl From<FourCC> for PixelFormat {
+    fn from(fourcc: FourCC) -> Self {
+        FOURCCS
+            .iter()
+            .find(|(code, _)| *code == fourcc)
+            .map(|(_, pixfmt)| pixfmt.clone())
+            .unwrap_or_else(|| PixelFormat::Custom(fourcc.to_string()))
+    }
+}
+
+impl TryFrom<&PixelFormat> for FourCC {
+    type Error = Error;
+
+    fn try_from(pixfmt: &PixelFormat) -> Result<Self, Self::Error> {
+        if let PixelFormat::Custom(repr) = pixfmt {
+            return repr.parse();
+        }
+
+        FOURCCS
+            .iter()
+            .find(|(_, other)| other == pixfmt)
+            .map(|(code, _)| *code)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::UnsupportedFormat,
+                    format!("no four character code for {}", pixfmt),
+                )
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Memory layout of a single image plane
+pub struct Plane {
+    /// Position of the first byte of the plane in the image buffer
+    pub offset: usize,
+    /// Length of a pixel row in bytes, including any padding
+    pub stride: usize,
+    /// Size of the plane in bytes
+    pub size: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 /// Image buffer format description
 pub struct ImageFormat {
     /// Width in pixels
@@ -57,12 +365,12 @@ pub struct ImageFormat {
     pub height: u32,
     /// PixelFormat
     pub pixfmt: PixelFormat,
-    /// Length of a pixel row in bytes
-    pub stride: Option<usize>,
+    /// Memory layout of the planes, empty for compressed and unknown formats
+    pub planes: Vec<Plane>,
 }
 
 impl ImageFormat {
-    /// Returns an image format representation
+    /// Returns an image format representation with tightly packed rows
     ///
     /// # Arguments
     ///
@@ -75,25 +383,218 @@ impl ImageFormat {
     /// ```
     /// use eye_hal::format::{ImageFormat, PixelFormat};
     /// let format = ImageFormat::new(1280, 720, PixelFormat::Rgb(24));
+    /// assert_eq!(format.planes[0].stride, 1280 * 3);
     /// ```
     pub fn new(width: u32, height: u32, pixfmt: PixelFormat) -> Self {
-        let stride = pixfmt.bits().map(|bits| (width * (bits / 8)) as usize);
+        let planes = planes(width, height, &pixfmt, None);
 
         ImageFormat {
             width,
             height,
             pixfmt,
-            stride,
+            planes,
         }
     }
 
     /// Builder pattern constructor
     ///
+    /// The strides of the other planes are derived from the one of the first plane, just like
+    /// V4L2 does for single-planar formats.
+    ///
     /// # Arguments
     ///
-    /// * `stride` - Length of a pixel row in bytes
+    /// * `stride` - Length of a pixel row of the first plane in bytes, including any padding
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eye_hal::format::{ImageFormat, PixelFormat};
+    /// let format = ImageFormat::new(640, 480, PixelFormat::Nv12(12)).stride(768);
+    /// assert_eq!(format.planes[1].offset, 768 * 480);
+    /// assert_eq!(format.size(), Some(768 * 480 * 3 / 2));
+    /// ```
     pub fn stride(mut self, stride: usize) -> Self {
-        self.stride = Some(stride);
+        self.planes = planes(self.width, self.height, &self.pixfmt, Some(stride));
         self
     }
+
+    /// Returns the size of the image buffer in bytes, if the layout is known
+    pub fn size(&self) -> Option<usize> {
+        self.planes
+            .iter()
+            .map(|plane| plane.offset + plane.size)
+            .max()
+    }
+}
+
+/// Returns the planes of an image, the stride of the first one defaults to the packed row length
+fn planes(width: u32, height: u32, pixfmt: &PixelFormat, stride: Option<usize>) -> Vec<Plane> {
+    let (width, height) = (width as usize, height as usize);
+    let chroma_height = height.div_ceil(2);
+
+    // number of bytes per pixel of the first plane and the chroma planes that follow it as
+    // (horizontal subsampling, interleaved samples, height) tuples
+    let (bpp, chroma): (usize, &[(usize, usize, usize)]) = match pixfmt {
+        PixelFormat::Yuyv(16) | PixelFormat::Uyvy(16) => (2, &[]),
+        PixelFormat::Yvyu(16) | PixelFormat::Vyuy(16) => (2, &[]),
+        PixelFormat::Uyv(24) => (3, &[]),
+        // planar 4:2:0, the chroma planes are subsampled in both directions
+        PixelFormat::I420(12) | PixelFormat::Yv12(12) => {
+            (1, &[(2, 1, chroma_height), (2, 1, chroma_height)])
+        }
+        // semi-planar formats interleave both chroma samples in one plane, so odd widths are
+        // rounded up to a whole sample pair
+        PixelFormat::Nv12(12) | PixelFormat::Nv21(12) => (1, &[(2, 2, chroma_height)]),
+        // 4:2:2, the chroma planes are subsampled horizontally
+        PixelFormat::Nv16(16) | PixelFormat::Nv61(16) => (1, &[(2, 2, height)]),
+        PixelFormat::I422(16) => (1, &[(2, 1, height), (2, 1, height)]),
+        PixelFormat::I444(24) => (1, &[(1, 1, height), (1, 1, height)]),
+        // samples of more than 8 bits are stored in 16 bit words
+        PixelFormat::Depth(bits) | PixelFormat::Gray(bits) | PixelFormat::Bayer(_, bits)
+            if *bits <= 16 =>
+        {
+            (bits.div_ceil(8) as usize, &[])
+        }
+        PixelFormat::Bgr(bits)
+        | PixelFormat::Rgb(bits)
+        | PixelFormat::Bgra(bits)
+        | PixelFormat::Rgba(bits)
+            if bits.is_multiple_of(8) =>
+        {
+            (*bits as usize / 8, &[])
+        }
+        PixelFormat::Rgb565 => (2, &[]),
+        _ => return Vec::new(),
+    };
+
+    let stride = stride.unwrap_or(width * bpp);
+    let mut planes = vec![Plane {
+        offset: 0,
+        stride,
+        size: stride * height,
+    }];
+    for (subsampling, samples, height) in chroma {
+        let offset = planes
+            .last()
+            .map(|plane| plane.offset + plane.size)
+            .unwrap();
+        let stride = stride.div_ceil(*subsampling) * samples;
+        planes.push(Plane {
+            offset,
+            stride,
+            size: stride * height,
+        });
+    }
+
+    planes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fourcc_roundtrip() {
+        for (i, (code, pixfmt)) in FOURCCS.iter().enumerate() {
+            // codes are unique, aliases map to a format listed before
+            assert!(
+                FOURCCS[..i].iter().all(|(other, _)| other != code),
+                "{}",
+                code
+            );
+            assert_eq!(PixelFormat::from(*code), *pixfmt);
+
+            let canonical = FourCC::try_from(pixfmt).unwrap();
+            assert_eq!(PixelFormat::from(canonical), *pixfmt);
+            assert_eq!(code.to_string().parse::<FourCC>().unwrap(), *code);
+        }
+    }
+
+    #[test]
+    fn fourcc_custom() {
+        let pixfmt = PixelFormat::from(FourCC::new(b"AB1 "));
+        assert_eq!(pixfmt, PixelFormat::Custom(String::from("AB1")));
+        assert_eq!(FourCC::try_from(&pixfmt).unwrap(), FourCC::new(b"AB1 "));
+
+        for repr in ["", "ABCDE", "A\tB"] {
+            let err = repr.parse::<FourCC>().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+        }
+        let err = FourCC::try_from(&PixelFormat::Gray(7)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnsupportedFormat);
+    }
+
+    #[test]
+    fn planes_of_planar_formats() {
+        let sizes = |pixfmt: PixelFormat| {
+            ImageFormat::new(6, 4, pixfmt)
+                .planes
+                .iter()
+                .map(|plane| (plane.offset, plane.stride, plane.size))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            sizes(PixelFormat::I420(12)),
+            [(0, 6, 24), (24, 3, 6), (30, 3, 6)]
+        );
+        assert_eq!(
+            sizes(PixelFormat::I422(16)),
+            [(0, 6, 24), (24, 3, 12), (36, 3, 12)]
+        );
+        assert_eq!(
+            sizes(PixelFormat::I444(24)),
+            [(0, 6, 24), (24, 6, 24), (48, 6, 24)]
+        );
+        assert_eq!(sizes(PixelFormat::Nv61(16)), [(0, 6, 24), (24, 6, 24)]);
+        assert_eq!(sizes(PixelFormat::Uyv(24)), [(0, 18, 72)]);
+    }
+
+    #[test]
+    fn planes_of_odd_widths() {
+        let sizes = |pixfmt: PixelFormat| {
+            ImageFormat::new(5, 3, pixfmt)
+                .planes
+                .iter()
+                .map(|plane| (plane.offset, plane.stride, plane.size))
+                .collect::<Vec<_>>()
+        };
+
+        // interleaved chroma rows hold whole sample pairs
+        assert_eq!(sizes(PixelFormat::Nv12(12)), [(0, 5, 15), (15, 6, 12)]);
+        assert_eq!(sizes(PixelFormat::Nv16(16)), [(0, 5, 15), (15, 6, 18)]);
+        assert_eq!(
+            sizes(PixelFormat::I420(12)),
+            [(0, 5, 15), (15, 3, 6), (21, 3, 6)]
+        );
+    }
+
+    #[test]
+    fn planar_444_has_no_fourcc() {
+        let err = FourCC::try_from(&PixelFormat::I444(24)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnsupportedFormat);
+        assert_eq!(
+            PixelFormat::from(FourCC::new(b"YM24")),
+            PixelFormat::Custom(String::from("YM24"))
+        );
+    }
+
+    #[test]
+    fn planes_of_deep_formats() {
+        // samples of more than 8 bits are stored in 16 bit words
+        for pixfmt in [
+            PixelFormat::Gray(10),
+            PixelFormat::Gray(12),
+            PixelFormat::Depth(16),
+        ] {
+            let format = ImageFormat::new(6, 4, pixfmt);
+            assert_eq!(format.planes[0].stride, 12);
+            assert_eq!(format.size(), Some(48));
+        }
+
+        assert!(ImageFormat::new(6, 4, PixelFormat::Jpeg).planes.is_empty());
+        assert!(ImageFormat::new(6, 4, PixelFormat::Rgb(30))
+            .planes
+            .is_empty());
+    }
 }
@@ -0,0 +1,75 @@
+//! Channel backed asynchronous streams
+//!
+//! Backends without a pollable file descriptor are driven by a worker thread which captures
+//! frames using the blocking stream API and sends them through a channel.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use crate::error::{ErrorKind, Result};
+use crate::stream::Frame;
+use crate::traits::Stream;
+
+/// Number of frames which may be buffered in the channel before the worker thread blocks
+const CAPACITY: usize = 2;
+
+/// Interval in which the worker thread checks whether the receiving end is still alive
+const HANGUP_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct Handle {
+    rx: mpsc::Receiver<Result<Frame<'static>>>,
+}
+
+impl Handle {
+    /// Creates a stream on a worker thread and starts forwarding its frames
+    ///
+    /// If the stream cannot be created, the error is yielded as the only item.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Function which creates the blocking stream
+    pub fn spawn<F, S>(f: F) -> Self
+    where
+        F: FnOnce() -> Result<S> + Send + 'static,
+        S: for<'a> Stream<'a, Item = Result<Frame<'a>>>,
+    {
+        let (tx, rx) = mpsc::channel(CAPACITY);
+
+        thread::spawn(move || {
+            let mut stream = match f() {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = tx.blocking_send(Err(e));
+                    return;
+                }
+            };
+
+            // Once the receiving end hung up, the stream is no longer needed. Frames are waited
+            // for with a timeout, so the worker notices even if no frames arrive.
+            while !tx.is_closed() {
+                let item = match stream.next_timeout(HANGUP_INTERVAL) {
+                    Some(Err(e)) if e.kind() == ErrorKind::Timeout => continue,
+                    Some(item) => item.map(Frame::into_owned),
+                    None => break,
+                };
+                if tx.blocking_send(item).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Handle { rx }
+    }
+}
+
+impl futures_core::Stream for Handle {
+    type Item = Result<Frame<'static>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().rx.poll_recv(cx)
+    }
+}
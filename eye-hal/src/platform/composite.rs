@@ -0,0 +1,278 @@
+//! Composite context spanning multiple backends
+//!
+//! A single camera may be reachable through several HALs at once, e.g. through v4l2 as well as
+//! through libuvc on Linux. The composite context merges the devices of all its children and
+//! dispatches [`open_device`](ContextTrait::open_device) to the backend responsible for the URI
+//! scheme.
+//!
+//! # Example
+//!
+//! ```
+//! use eye_hal::platform::{composite, file, mock, Context};
+//! use eye_hal::traits::{Context as _, Device as _};
+//!
+//! let ctx = composite::Context::new()
+//!     .context(Context::File(file::Context {}))
+//!     .context(Context::Mock(mock::Context::default()));
+//!
+//! let devices = ctx.devices().unwrap();
+//! assert_eq!(devices[0].uri, "mock://0");
+//!
+//! let dev = ctx.open_device(&devices[0].uri).unwrap();
+//! assert!(!dev.streams().unwrap().is_empty());
+//! ```
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::device;
+use crate::error::{Error, ErrorKind, Result};
+use crate::platform::{Context as PlatformContext, Device as PlatformDevice};
+use crate::traits::Context as ContextTrait;
+
+/// Time to wait for events of a child watcher before checking whether the watcher is still alive
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Runtime context
+///
+/// Children are consulted in the order they were added. If several of them describe the same
+/// physical USB device, only the description of the first one is kept.
+#[derive(Default)]
+pub struct Context<'a> {
+    contexts: Vec<PlatformContext<'a>>,
+}
+
+impl<'a> Context<'a> {
+    /// Returns a context without any children
+    pub fn new() -> Self {
+        Context {
+            contexts: Vec::new(),
+        }
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Child context, takes precedence over all children added after it
+    pub fn context(mut self, ctx: PlatformContext<'a>) -> Self {
+        self.contexts.push(ctx);
+        self
+    }
+
+    /// Returns the child contexts
+    pub fn contexts(&self) -> &[PlatformContext<'a>] {
+        &self.contexts
+    }
+}
+
+impl<'a> ContextTrait<'a> for Context<'a> {
+    type Device = PlatformDevice<'a>;
+
+    fn devices(&self) -> Result<Vec<device::Description>> {
+        let mut devices: Vec<device::Description> = Vec::new();
+
+        for ctx in &self.contexts {
+            // A backend which fails to enumerate (e.g. because of missing permissions) should not
+            // hide the devices of the others.
+            let descriptions = match ctx.devices() {
+                Ok(descriptions) => descriptions,
+                Err(_) => continue,
+            };
+
+            // Only deduplicate across backends: one USB device may well expose multiple capture
+            // nodes through the same backend, e.g. a color and an infrared camera.
+            let seen = devices.len();
+            for desc in descriptions {
+                if let Some(key) = usb_key(&desc) {
+                    if devices[..seen].iter().any(|d| usb_key(d) == Some(key)) {
+                        continue;
+                    }
+                }
+                devices.push(desc);
+            }
+        }
+
+        Ok(devices)
+    }
+
+    fn open_device(&self, uri: &str) -> Result<Self::Device> {
+        let scheme = match uri.find("://") {
+            Some(pos) => &uri[..pos],
+            None => return Err(Error::new(ErrorKind::InvalidArgument, "invalid URI")),
+        };
+
+        // Contexts which claim the scheme are authoritative. Only if there is none, the contexts
+        // without a fixed scheme (e.g. custom ones) get a chance to open the device.
+        let mut candidates: Vec<&PlatformContext<'a>> = self
+            .contexts
+            .iter()
+            .filter(|ctx| ctx.scheme() == Some(scheme))
+            .collect();
+        if candidates.is_empty() {
+            candidates = self
+                .contexts
+                .iter()
+                .filter(|ctx| ctx.scheme().is_none())
+                .collect();
+        }
+
+        let mut error = None;
+        for ctx in candidates {
+            match ctx.open_device(uri) {
+                Ok(dev) => return Ok(dev),
+                // report the error of the most specific context
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+
+        Err(error.unwrap_or_else(|| {
+            Error::new(
+                ErrorKind::NotSupported,
+                format!("no context for URI scheme {}", scheme),
+            )
+        }))
+    }
+
+    fn watch(&self) -> Result<device::Watcher> {
+        let watchers: Vec<device::Watcher> = self
+            .contexts
+            .iter()
+            .filter_map(|ctx| ctx.watch().ok())
+            .collect();
+        if watchers.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotSupported,
+                "hotplug monitoring is not supported",
+            ));
+        }
+
+        let (watcher, notifier) = device::Watcher::new();
+        let forwarded = Arc::new(Mutex::new(Forwarded::default()));
+        for (index, child) in watchers.into_iter().enumerate() {
+            let notifier = notifier.clone();
+            let forwarded = Arc::clone(&forwarded);
+            thread::spawn(move || forward(index, child, notifier, forwarded));
+        }
+
+        Ok(watcher)
+    }
+}
+
+/// Forwards the events of a child watcher until either side is gone
+///
+/// The child stops being watched if it reports an error other than a timeout.
+fn forward(
+    index: usize,
+    mut child: device::Watcher,
+    notifier: device::Notifier,
+    forwarded: Arc<Mutex<Forwarded>>,
+) {
+    while !notifier.is_closed() {
+        let event = match child.next_timeout(POLL_INTERVAL) {
+            Some(Ok(event)) => event,
+            Some(Err(e)) if e.kind() == ErrorKind::Timeout => continue,
+            Some(Err(_)) | None => return,
+        };
+
+        let forward = forwarded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .filter(index, &event);
+        if forward && !notifier.notify(event) {
+            return;
+        }
+    }
+}
+
+#[derive(Default)]
+/// Bookkeeping of the devices reported to the watcher
+///
+/// Just like [`Context::devices`], a USB device reported by several children is only announced
+/// once, by the child which reports it first.
+struct Forwarded {
+    /// Child, URI and USB device of every announced device
+    announced: Vec<(usize, String, Option<UsbKey>)>,
+    /// URIs of the devices which were not announced because another child did so already
+    hidden: Vec<String>,
+}
+
+impl Forwarded {
+    /// Records an event of a child, returns true if it should be forwarded
+    fn filter(&mut self, index: usize, event: &device::Event) -> bool {
+        match event {
+            device::Event::Added(desc) => {
+                let key = usb_key(desc);
+                let duplicate = key.is_some()
+                    && self
+                        .announced
+                        .iter()
+                        .any(|(other, _, other_key)| *other != index && *other_key == key);
+                if duplicate {
+                    self.hidden.push(desc.uri.clone());
+                } else {
+                    self.announced.push((index, desc.uri.clone(), key));
+                }
+                !duplicate
+            }
+            device::Event::Removed(uri) => {
+                if let Some(pos) = self.hidden.iter().position(|hidden| hidden == uri) {
+                    self.hidden.remove(pos);
+                    return false;
+                }
+                self.announced.retain(|(_, announced, _)| announced != uri);
+                true
+            }
+        }
+    }
+}
+
+/// Vendor ID, product ID, bus number and address of a USB device
+type UsbKey = (u16, u16, u8, u8);
+
+/// Identifies the physical USB device, which is the same no matter which backend describes it
+fn usb_key(desc: &device::Description) -> Option<UsbKey> {
+    desc.usb
+        .as_ref()
+        .map(|usb| (usb.vendor_id, usb.product_id, usb.bus_number, usb.address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn added(uri: &str, address: u8) -> device::Event {
+        device::Event::Added(device::Description {
+            uri: uri.to_string(),
+            usb: Some(device::Usb {
+                vendor_id: 0x046d,
+                product_id: 0x0825,
+                address,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn forward_once_per_usb_device() {
+        let mut forwarded = Forwarded::default();
+
+        assert!(forwarded.filter(0, &added("v4l:///dev/video0", 1)));
+        // the same child may expose several nodes of one device
+        assert!(forwarded.filter(0, &added("v4l:///dev/video2", 1)));
+        // another child reporting the same device is hidden, including its removal
+        assert!(!forwarded.filter(1, &added("uvc://1", 1)));
+        assert!(!forwarded.filter(1, &device::Event::Removed("uvc://1".to_string())));
+        // other devices are forwarded
+        assert!(forwarded.filter(1, &added("uvc://2", 2)));
+
+        assert!(forwarded.filter(0, &device::Event::Removed("v4l:///dev/video0".to_string())));
+        assert!(forwarded.filter(0, &device::Event::Removed("v4l:///dev/video2".to_string())));
+        // once the device is gone, it can be announced by any child
+        assert!(forwarded.filter(1, &added("uvc://1", 1)));
+    }
+}
@@ -0,0 +1,20 @@
+use crate::device;
+use crate::error::Result;
+use crate::platform::file::device::Handle as DeviceHandle;
+use crate::traits::Context as ContextTrait;
+
+/// Runtime context
+pub struct Context {}
+
+impl<'a> ContextTrait<'a> for Context {
+    type Device = DeviceHandle;
+
+    fn devices(&self) -> Result<Vec<device::Description>> {
+        // Files cannot be enumerated, they have to be opened by their URI.
+        Ok(Vec::new())
+    }
+
+    fn open_device(&self, uri: &str) -> Result<Self::Device> {
+        DeviceHandle::with_uri(uri)
+    }
+}
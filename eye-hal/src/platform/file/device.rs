@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use crate::control;
+use crate::error::{Error, ErrorKind, Result};
+use crate::platform::file::source::{self, Options, Source};
+use crate::platform::file::stream::Handle as StreamHandle;
+use crate::stream;
+use crate::traits::Device;
+
+pub struct Handle {
+    path: PathBuf,
+    opts: Options,
+    desc: stream::Descriptor,
+}
+
+impl Handle {
+    pub fn with_uri<S: AsRef<str>>(uri: S) -> Result<Self> {
+        let (path, opts) = source::parse_uri(uri.as_ref())?;
+
+        // probe the file contents
+        let desc = Source::open(&path, &opts)?.descriptor().clone();
+
+        Ok(Handle { path, opts, desc })
+    }
+}
+
+impl<'a> Device<'a> for Handle {
+    type Stream = StreamHandle;
+
+    fn streams(&self) -> Result<Vec<stream::Descriptor>> {
+        Ok(vec![self.desc.clone()])
+    }
+
+    fn start_stream(&self, desc: &stream::Descriptor) -> Result<Self::Stream> {
+        // Files can only be played back in their native format, but the frame rate is up to the
+        // caller.
+        if desc.width != self.desc.width
+            || desc.height != self.desc.height
+            || desc.pixfmt != self.desc.pixfmt
+        {
+            return Err(Error::new(
+                ErrorKind::UnsupportedFormat,
+                "stream not supported by file",
+            ));
+        }
+
+        let source = Source::open(&self.path, &self.opts)?;
+        Ok(StreamHandle::new(
+            source,
+            desc.interval,
+            self.opts.looping,
+            self.opts.realtime,
+        ))
+    }
+
+    fn controls(&self) -> Result<Vec<control::Descriptor>> {
+        Ok(Vec::new())
+    }
+
+    fn control(&self, _id: u32) -> Result<control::State> {
+        Err(Error::new(ErrorKind::NotFound, "no such control"))
+    }
+
+    fn set_control(&mut self, _id: u32, _val: &control::State) -> Result<()> {
+        Err(Error::new(ErrorKind::NotFound, "no such control"))
+    }
+}
@@ -0,0 +1,54 @@
+//! File playback backend
+//!
+//! Treats recorded footage as a camera, which is mostly useful to replay captured sessions in
+//! regression tests. The following sources are supported:
+//!
+//! * A directory of JPEG or PNG images (PNG requires the `png` feature), played back in the order
+//!   of their file names. JPEG images are passed through as they are.
+//! * A single JPEG or PNG image.
+//! * A YUV4MPEG2 (`.y4m`) file.
+//! * An MJPEG (`.mjpeg`, `.mjpg`) file, i.e. concatenated JPEG images.
+//! * A raw frame dump (any other file), i.e. concatenated uncompressed frames.
+//!
+//! Devices are addressed by their path, e.g. `file:///home/user/session.y4m`. Reserved characters
+//! in the path have to be percent-encoded, e.g. `file:///home/user/my%20session.y4m`. Additional
+//! options can be passed as URI query parameters:
+//!
+//! * `loop` - Restart at the first frame once the end is reached (default: `false`). Otherwise,
+//!   the stream ends after the last frame.
+//! * `realtime` - Pace frames according to the stream interval (default: `true`). Set it to
+//!   `false` to read frames as fast as possible.
+//! * `fps` - Frame rate, overrides the rate stored in the file (default: 30 if not stored). Rates
+//!   can be given as exact fraction, e.g. `30000/1001` for NTSC, or as decimal number.
+//! * `width`, `height`, `format` - Frame width, height and four character code (e.g. `YUYV`).
+//!   Required for raw frame dumps since they do not carry any format information.
+//! * `size` - Frame size in bytes, for raw frame dumps of formats unknown to this crate.
+//!
+//! # Example
+//!
+//! ```
+//! use eye_hal::platform::{file, Context};
+//! use eye_hal::traits::{Context as _, Device as _, Stream as _};
+//!
+//! // two 4x2 grayscale frames
+//! let path = std::env::temp_dir().join("eye-hal-file-example.raw");
+//! std::fs::write(&path, [0u8; 16]).unwrap();
+//!
+//! let ctx = Context::File(file::Context {});
+//! let uri = format!("file://{}?width=4&height=2&format=GREY", path.display());
+//! let dev = ctx.open_device(&uri).unwrap();
+//! let desc = dev.streams().unwrap()[0].clone();
+//! let mut stream = dev.start_stream(&desc).unwrap();
+//!
+//! assert_eq!(stream.next().unwrap().unwrap().len(), 8);
+//! assert_eq!(stream.next().unwrap().unwrap().len(), 8);
+//! assert!(stream.next().is_none());
+//! ```
+
+pub mod context;
+pub mod device;
+pub mod stream;
+
+mod source;
+
+pub use context::Context;
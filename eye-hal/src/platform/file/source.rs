@@ -0,0 +1,848 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::format::{FourCC, ImageFormat, PixelFormat};
+use crate::stream::{self, Descriptor, Interval};
+
+/// Playback options given as URI query parameters
+#[derive(Clone, Debug)]
+pub(crate) struct Options {
+    /// Restart at the first frame once the end is reached
+    pub looping: bool,
+    /// Pace frames according to the stream interval instead of reading them as fast as possible
+    pub realtime: bool,
+    /// Frame width (raw dumps only)
+    pub width: Option<u32>,
+    /// Frame height (raw dumps only)
+    pub height: Option<u32>,
+    /// Four character code of the frame format (raw dumps only)
+    pub format: Option<String>,
+    /// Frame size in bytes (raw dumps only)
+    pub size: Option<usize>,
+    /// Frame interval (overrides the rate stored in the file, if any)
+    pub fps: Option<Interval>,
+}
+
+/// Splits a `file://` URI into the path and the playback options
+///
+/// The path and the parameters are percent-decoded, so paths containing reserved characters such
+/// as `%`, `?` or spaces have to be encoded, e.g. `file:///tmp/my%20session.y4m`.
+pub(crate) fn parse_uri(uri: &str) -> Result<(PathBuf, Options)> {
+    let uri = if let Some(uri) = uri.strip_prefix("file://") {
+        uri
+    } else {
+        return Err(Error::new(ErrorKind::InvalidArgument, "invalid URI"));
+    };
+
+    let (path, query) = match uri.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (uri, ""),
+    };
+    if path.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidArgument,
+            "missing path in URI",
+        ));
+    }
+    let path = percent_decode(path)?;
+
+    let params = query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (key, val) = param.split_once('=').unwrap_or((param, ""));
+            Ok((percent_decode(key)?, percent_decode(val)?))
+        })
+        .collect::<Result<HashMap<String, String>>>()?;
+
+    fn parse<T: std::str::FromStr>(
+        params: &HashMap<String, String>,
+        key: &str,
+    ) -> Result<Option<T>> {
+        match params.get(key) {
+            Some(val) => match val.parse::<T>() {
+                Ok(val) => Ok(Some(val)),
+                Err(_) => Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    format!("malformed parameter in URI: {}", key),
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+
+    let opts = Options {
+        looping: parse(&params, "loop")?.unwrap_or(false),
+        realtime: parse(&params, "realtime")?.unwrap_or(true),
+        width: parse(&params, "width")?,
+        height: parse(&params, "height")?,
+        format: parse(&params, "format")?,
+        size: parse(&params, "size")?,
+        fps: match params.get("fps") {
+            Some(val) => Some(rate(val).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidArgument,
+                    "malformed parameter in URI: fps",
+                )
+            })?),
+            None => None,
+        },
+    };
+
+    Ok((PathBuf::from(path), opts))
+}
+
+/// Decodes `%XX` escape sequences as defined in RFC 3986
+fn percent_decode(input: &str) -> Result<String> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidArgument,
+            format!("malformed percent-encoding in URI: {}", input),
+        )
+    };
+
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut iter = input.bytes();
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let hex = [iter.next(), iter.next()];
+        let digits = hex
+            .iter()
+            .map(|digit| match digit {
+                Some(digit) => (*digit as char).to_digit(16).ok_or_else(invalid),
+                None => Err(invalid()),
+            })
+            .collect::<Result<Vec<u32>>>()?;
+        bytes.push((digits[0] * 16 + digits[1]) as u8);
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Frame source backed by one or more files
+pub(crate) struct Source {
+    desc: Descriptor,
+    kind: Kind,
+}
+
+enum Kind {
+    /// One image per file
+    Images { files: Vec<PathBuf>, next: usize },
+    /// Headerless concatenation of frames
+    Raw {
+        reader: BufReader<File>,
+        frame_size: usize,
+    },
+    /// YUV4MPEG2 stream
+    Y4m {
+        reader: BufReader<File>,
+        start: u64,
+        frame_size: usize,
+    },
+    /// Concatenation of JPEG images
+    Mjpeg {
+        data: Vec<u8>,
+        frames: Vec<Range<usize>>,
+        next: usize,
+    },
+}
+
+impl Source {
+    /// Opens a file or directory and detects its contents
+    ///
+    /// Directories are treated as image sequences (sorted by file name). Single files are
+    /// detected by their extension: `.y4m`, `.mjpeg`/`.mjpg` and image files are supported,
+    /// anything else is treated as a raw frame dump.
+    pub fn open(path: &Path, opts: &Options) -> Result<Self> {
+        if path.is_dir() {
+            let mut files: Vec<PathBuf> = fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && image_kind(path).is_some())
+                .collect();
+            files.sort();
+            return Self::images(files, opts);
+        }
+
+        match extension(path).as_deref() {
+            Some("y4m") => Self::y4m(path, opts),
+            Some("mjpeg") | Some("mjpg") => Self::mjpeg(path, opts),
+            _ if image_kind(path).is_some() => Self::images(vec![path.to_path_buf()], opts),
+            _ => Self::raw(path, opts),
+        }
+    }
+
+    /// Returns the format of the frames read from the source
+    pub fn format(&self) -> stream::Format {
+        let mut format = stream::Format::packed(&self.desc);
+        match &self.kind {
+            // the frame size may have been given explicitly for raw dumps
+            Kind::Raw { frame_size, .. } | Kind::Y4m { frame_size, .. } => {
+                format.size = *frame_size
+            }
+            Kind::Mjpeg { frames, .. } => {
+                format.size = frames.iter().map(|frame| frame.len()).max().unwrap_or(0)
+            }
+            Kind::Images { .. } => {}
+        }
+        format
+    }
+
+    fn images(files: Vec<PathBuf>, opts: &Options) -> Result<Self> {
+        let first = if let Some(first) = files.first() {
+            first
+        } else {
+            return Err(Error::new(ErrorKind::NotFound, "no images found"));
+        };
+
+        let data = fs::read(first)?;
+        let (width, height, pixfmt) = match image_kind(first) {
+            Some(ImageKind::Jpeg) => {
+                let (width, height) = jpeg_size(&data)
+                    .ok_or_else(|| Error::new(ErrorKind::Other, "malformed JPEG image"))?;
+                (width, height, PixelFormat::Jpeg)
+            }
+            Some(ImageKind::Png) => png_format(&data)?,
+            None => unreachable!("non-image files are filtered"),
+        };
+
+        Ok(Source {
+            desc: Descriptor {
+                width,
+                height,
+                pixfmt,
+                interval: opts.fps.unwrap_or_else(|| Interval::from_fps(30)),
+            },
+            kind: Kind::Images { files, next: 0 },
+        })
+    }
+
+    fn raw(path: &Path, opts: &Options) -> Result<Self> {
+        let (width, height, fourcc) = match (opts.width, opts.height, &opts.format) {
+            (Some(width), Some(height), Some(fourcc)) => (width, height, fourcc),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    "raw files require the width, height and format URI parameters",
+                ))
+            }
+        };
+
+        let pixfmt = PixelFormat::from(fourcc.parse::<FourCC>()?);
+        let frame_size = match opts.size.or_else(|| frame_size(width, height, &pixfmt)) {
+            Some(size) if size > 0 => size,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    format!(
+                        "unknown frame size for {}, use the size URI parameter",
+                        pixfmt
+                    ),
+                ))
+            }
+        };
+
+        Ok(Source {
+            desc: Descriptor {
+                width,
+                height,
+                pixfmt,
+                interval: opts.fps.unwrap_or_else(|| Interval::from_fps(30)),
+            },
+            kind: Kind::Raw {
+                reader: BufReader::new(File::open(path)?),
+                frame_size,
+            },
+        })
+    }
+
+    fn y4m(path: &Path, opts: &Options) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut header = Vec::new();
+        reader.read_until(b'\n', &mut header)?;
+        let header = String::from_utf8_lossy(&header);
+
+        let mut params = header.split_whitespace();
+        if params.next() != Some("YUV4MPEG2") {
+            return Err(Error::new(ErrorKind::Other, "not a YUV4MPEG2 file"));
+        }
+
+        let (mut width, mut height) = (None, None);
+        let mut rate = Interval::from_fps(30);
+        let mut colorspace = "420jpeg";
+        for param in params {
+            let (tag, val) = match (param.get(..1), param.get(1..)) {
+                (Some(tag), Some(val)) => (tag, val),
+                _ => continue,
+            };
+            match tag {
+                "W" => width = val.parse::<u32>().ok(),
+                "H" => height = val.parse::<u32>().ok(),
+                "F" => {
+                    if let Some((num, den)) = val.split_once(':') {
+                        if let (Ok(num), Ok(den)) = (num.parse::<u32>(), den.parse::<u32>()) {
+                            // the header stores the frame rate, e.g. 30000:1001 for NTSC
+                            if num > 0 && den > 0 {
+                                rate = Interval::new(den, num);
+                            }
+                        }
+                    }
+                }
+                "C" => colorspace = val,
+                _ => {}
+            }
+        }
+
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err(Error::new(ErrorKind::Other, "missing frame size in header")),
+        };
+
+        let pixfmt = match colorspace {
+            "420jpeg" | "420paldv" | "420mpeg2" | "420" => PixelFormat::I420(12),
+            "422" => PixelFormat::I422(16),
+            "444" => PixelFormat::I444(24),
+            "mono" => PixelFormat::Gray(8),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::UnsupportedFormat,
+                    format!("unsupported Y4M colorspace: {}", colorspace),
+                ))
+            }
+        };
+        let frame_size = frame_size(width, height, &pixfmt).unwrap();
+        let start = reader.stream_position()?;
+
+        Ok(Source {
+            desc: Descriptor {
+                width,
+                height,
+                pixfmt,
+                interval: opts.fps.unwrap_or(rate),
+            },
+            kind: Kind::Y4m {
+                reader,
+                start,
+                frame_size,
+            },
+        })
+    }
+
+    fn mjpeg(path: &Path, opts: &Options) -> Result<Self> {
+        let data = fs::read(path)?;
+
+        let mut frames = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            // skip any garbage in between images
+            match data[offset..].windows(2).position(|w| w == [0xff, 0xd8]) {
+                Some(pos) => offset += pos,
+                None => break,
+            }
+
+            match jpeg_len(&data[offset..]) {
+                Some(len) => {
+                    frames.push(offset..offset + len);
+                    offset += len;
+                }
+                None => break,
+            }
+        }
+
+        let (width, height) = match frames.first() {
+            Some(range) => jpeg_size(&data[range.clone()])
+                .ok_or_else(|| Error::new(ErrorKind::Other, "malformed JPEG image"))?,
+            None => return Err(Error::new(ErrorKind::NotFound, "no JPEG images found")),
+        };
+
+        Ok(Source {
+            desc: Descriptor {
+                width,
+                height,
+                pixfmt: PixelFormat::Jpeg,
+                interval: opts.fps.unwrap_or_else(|| Interval::from_fps(30)),
+            },
+            kind: Kind::Mjpeg {
+                data,
+                frames,
+                next: 0,
+            },
+        })
+    }
+
+    /// Returns the native stream of the source
+    pub fn descriptor(&self) -> &Descriptor {
+        &self.desc
+    }
+
+    /// Reads the next frame into a buffer, returns false once the end is reached
+    pub fn read(&mut self, buf: &mut Vec<u8>) -> Result<bool> {
+        match &mut self.kind {
+            Kind::Images { files, next } => {
+                let path = match files.get(*next) {
+                    Some(path) => path,
+                    None => return Ok(false),
+                };
+                *next += 1;
+
+                let data = fs::read(path)?;
+                match (image_kind(path), &self.desc.pixfmt) {
+                    (Some(ImageKind::Jpeg), PixelFormat::Jpeg) => {
+                        *buf = data;
+                    }
+                    (Some(ImageKind::Png), _) => {
+                        let (width, height, pixfmt) = png_format(&data)?;
+                        if (width, height, &pixfmt)
+                            != (self.desc.width, self.desc.height, &self.desc.pixfmt)
+                        {
+                            return Err(Error::new(
+                                ErrorKind::UnsupportedFormat,
+                                "image format mismatch",
+                            ));
+                        }
+                        png_decode(&data, buf)?;
+                    }
+                    _ => {
+                        return Err(Error::new(
+                            ErrorKind::UnsupportedFormat,
+                            "image format mismatch",
+                        ))
+                    }
+                }
+                Ok(true)
+            }
+            Kind::Raw { reader, frame_size } => read_frame(reader, *frame_size, buf),
+            Kind::Y4m {
+                reader, frame_size, ..
+            } => {
+                let mut header = Vec::new();
+                if reader.read_until(b'\n', &mut header)? == 0 {
+                    return Ok(false);
+                }
+                if !header.starts_with(b"FRAME") {
+                    return Err(Error::new(ErrorKind::Other, "malformed Y4M frame header"));
+                }
+                read_frame(reader, *frame_size, buf)
+            }
+            Kind::Mjpeg { data, frames, next } => {
+                let range = match frames.get(*next) {
+                    Some(range) => range.clone(),
+                    None => return Ok(false),
+                };
+                *next += 1;
+
+                buf.clear();
+                buf.extend_from_slice(&data[range]);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Seeks back to the first frame
+    pub fn rewind(&mut self) -> Result<()> {
+        match &mut self.kind {
+            Kind::Images { next, .. } | Kind::Mjpeg { next, .. } => *next = 0,
+            Kind::Raw { reader, .. } => {
+                reader.seek(SeekFrom::Start(0))?;
+            }
+            Kind::Y4m { reader, start, .. } => {
+                reader.seek(SeekFrom::Start(*start))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads exactly one frame, a truncated frame at the end of the file is treated like the end
+fn read_frame<R: Read>(reader: &mut R, frame_size: usize, buf: &mut Vec<u8>) -> Result<bool> {
+    buf.resize(frame_size, 0);
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Converts a frame rate given as URI parameter to an interval
+///
+/// Rates are given as exact fraction, e.g. `30000/1001`, or as decimal number, which is kept to
+/// 1/1000 fps.
+fn rate(val: &str) -> Option<Interval> {
+    if let Some((num, den)) = val.split_once('/') {
+        let (num, den) = (num.parse::<u32>().ok()?, den.parse::<u32>().ok()?);
+        if num == 0 || den == 0 {
+            return None;
+        }
+        return Some(Interval::new(den, num));
+    }
+
+    let fps = val.parse::<f64>().ok()?;
+    if !fps.is_finite() || fps <= 0.0 || fps > u32::MAX as f64 / 1000.0 {
+        return None;
+    }
+
+    if fps.fract() == 0.0 {
+        return Some(Interval::from_fps(fps as u32));
+    }
+
+    let (num, den) = (1000, (fps * 1000.0).round() as u32);
+    let gcd = gcd(num, den);
+    Some(Interval::new(num / gcd, den / gcd))
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a.max(1)
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+enum ImageKind {
+    Jpeg,
+    Png,
+}
+
+fn image_kind(path: &Path) -> Option<ImageKind> {
+    match extension(path).as_deref() {
+        Some("jpg") | Some("jpeg") => Some(ImageKind::Jpeg),
+        Some("png") => Some(ImageKind::Png),
+        _ => None,
+    }
+}
+
+/// Returns the size of an uncompressed frame in bytes
+fn frame_size(width: u32, height: u32, pixfmt: &PixelFormat) -> Option<usize> {
+    ImageFormat::new(width, height, pixfmt.clone()).size()
+}
+
+/// Returns the length of the JPEG image at the start of a buffer
+fn jpeg_len(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+
+    let mut i = 2;
+    while i + 1 < data.len() {
+        if data[i] != 0xff {
+            return None;
+        }
+
+        let marker = data[i + 1];
+        match marker {
+            // fill byte
+            0xff => {
+                i += 1;
+                continue;
+            }
+            // EOI
+            0xd9 => return Some(i + 2),
+            // standalone markers without a payload
+            0x01 | 0xd0..=0xd7 => {
+                i += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        if i + 4 > data.len() {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        i += 2 + len;
+
+        if marker == 0xda {
+            // Entropy coded data follows the scan header. It may not contain any markers other
+            // than RST, so skip to the next marker.
+            while i + 1 < data.len() {
+                let next = data[i + 1];
+                if data[i] == 0xff && next != 0x00 && !(0xd0..=0xd7).contains(&next) {
+                    break;
+                }
+                i += 1;
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the frame size of a JPEG image
+fn jpeg_size(data: &[u8]) -> Option<(u32, u32)> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+
+    let mut i = 2;
+    while i + 4 <= data.len() {
+        if data[i] != 0xff {
+            return None;
+        }
+
+        let marker = data[i + 1];
+        if marker == 0xff {
+            i += 1;
+            continue;
+        }
+
+        // SOF markers, except for DHT, JPG and DAC which share the same range
+        if (0xc0..=0xcf).contains(&marker) && ![0xc4, 0xc8, 0xcc].contains(&marker) {
+            if i + 9 > data.len() {
+                return None;
+            }
+            let height = u16::from_be_bytes([data[i + 5], data[i + 6]]) as u32;
+            let width = u16::from_be_bytes([data[i + 7], data[i + 8]]) as u32;
+            return Some((width, height));
+        }
+
+        // the frame header must come before the first scan
+        if marker == 0xda || marker == 0xd9 {
+            return None;
+        }
+
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        i += 2 + len;
+    }
+
+    None
+}
+
+#[cfg(feature = "png")]
+fn png_reader(data: &[u8]) -> Result<png::Reader<io::Cursor<&[u8]>>> {
+    let mut decoder = png::Decoder::new(io::Cursor::new(data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    decoder
+        .read_info()
+        .map_err(|e| Error::new(ErrorKind::Other, e))
+}
+
+#[cfg(feature = "png")]
+fn png_format(data: &[u8]) -> Result<(u32, u32, PixelFormat)> {
+    let reader = png_reader(data)?;
+    let (width, height) = reader.info().size();
+    let pixfmt = match reader.output_color_type().0 {
+        png::ColorType::Grayscale => PixelFormat::Gray(8),
+        png::ColorType::Rgb => PixelFormat::Rgb(24),
+        png::ColorType::Rgba => PixelFormat::Rgba(32),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::UnsupportedFormat,
+                "unsupported PNG color type",
+            ))
+        }
+    };
+
+    Ok((width, height, pixfmt))
+}
+
+#[cfg(feature = "png")]
+fn png_decode(data: &[u8], buf: &mut Vec<u8>) -> Result<()> {
+    let mut reader = png_reader(data)?;
+    let size = reader
+        .output_buffer_size()
+        .ok_or_else(|| Error::new(ErrorKind::Other, "PNG image too large"))?;
+    buf.resize(size, 0);
+    reader
+        .next_frame(buf)
+        .map_err(|e| Error::new(ErrorKind::Other, e))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "png"))]
+fn png_format(_data: &[u8]) -> Result<(u32, u32, PixelFormat)> {
+    Err(Error::new(
+        ErrorKind::NotSupported,
+        "PNG support requires the png feature",
+    ))
+}
+
+#[cfg(not(feature = "png"))]
+fn png_decode(_data: &[u8], _buf: &mut Vec<u8>) -> Result<()> {
+    Err(Error::new(
+        ErrorKind::NotSupported,
+        "PNG support requires the png feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a temporary file and opens it as a source
+    fn open(name: &str, data: &[u8]) -> Result<Source> {
+        let path = std::env::temp_dir().join(format!("eye-file-{}-{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        let (_, opts) = parse_uri("file:///dev/null").unwrap();
+        let source = Source::open(&path, &opts);
+        fs::remove_file(&path).unwrap();
+        source
+    }
+
+    /// Returns a JPEG image with a frame header, a scan and no actual image data
+    fn jpeg(width: u16, height: u16, scan: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xff, 0xd8];
+        // APP segment whose payload looks like markers, it has to be skipped by its length
+        data.extend_from_slice(&[0xff, 0xe0, 0x00, 0x04, 0xd8, 0xd9]);
+        data.extend_from_slice(&[0xff, 0xc0, 0x00, 0x0b, 0x08]);
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&[0x01, 0x01, 0x11, 0x00]);
+        data.extend_from_slice(&[0xff, 0xda, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3f, 0x00]);
+        data.extend_from_slice(scan);
+        data.extend_from_slice(&[0xff, 0xd9]);
+        data
+    }
+
+    #[test]
+    fn uri_percent_decoding() {
+        let (path, opts) =
+            parse_uri("file:///tmp/my%20session%3F.y4m?format=YU%31%32&fps=12.5").unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/my session?.y4m"));
+        assert_eq!(opts.format.as_deref(), Some("YU12"));
+        assert_eq!(opts.fps, Some(Interval::new(2, 25)));
+        assert!(opts.realtime && !opts.looping);
+
+        // multi-byte UTF-8 sequences
+        let (path, _) = parse_uri("file:///tmp/caf%C3%A9").unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/café"));
+    }
+
+    #[test]
+    fn uri_rates() {
+        let fps = |val: &str| {
+            let (_, opts) = parse_uri(&format!("file:///tmp/a?fps={}", val)).unwrap();
+            opts.fps.unwrap()
+        };
+
+        assert_eq!(fps("25"), Interval::from_fps(25));
+        // exact NTSC rate, unlike its rounded decimal form
+        assert_eq!(fps("30000/1001"), Interval::new(1001, 30000));
+        assert_eq!(fps("29.97"), Interval::new(100, 2997));
+        assert_ne!(fps("29.97"), fps("30000/1001"));
+    }
+
+    #[test]
+    fn uri_errors() {
+        for uri in [
+            "mock://0",
+            "file://",
+            "file:///tmp/a%2",
+            "file:///tmp/a%zz",
+            "file:///tmp/a%ff",
+            "file:///tmp/a?fps=x",
+            "file:///tmp/a?fps=0",
+            "file:///tmp/a?fps=30/0",
+            "file:///tmp/a?fps=1.5/2",
+        ] {
+            let err = parse_uri(uri).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidArgument, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn jpeg_scan() {
+        // entropy coded data with stuffed bytes and restart markers
+        let data = jpeg(640, 480, &[0x12, 0xff, 0x00, 0x34, 0xff, 0xd0, 0x56]);
+        assert_eq!(jpeg_len(&data), Some(data.len()));
+        assert_eq!(jpeg_size(&data), Some((640, 480)));
+
+        // trailing data does not belong to the image
+        let mut padded = data.clone();
+        padded.extend_from_slice(&[0x00, 0xff, 0xd8]);
+        assert_eq!(jpeg_len(&padded), Some(data.len()));
+
+        // truncated images
+        assert_eq!(jpeg_len(&data[..data.len() - 1]), None);
+        assert_eq!(jpeg_size(&data[..12]), None);
+        assert_eq!(jpeg_len(&[0x00, 0xd8]), None);
+    }
+
+    #[test]
+    fn mjpeg_frames() {
+        let first = jpeg(32, 16, &[0x01, 0x02]);
+        let second = jpeg(32, 16, &[0x03, 0xff, 0x00]);
+        let mut data = first.clone();
+        // garbage in between images is skipped
+        data.extend_from_slice(&[0xde, 0xad]);
+        data.extend_from_slice(&second);
+
+        let mut source = open("frames.mjpeg", &data).unwrap();
+        assert_eq!(source.descriptor().width, 32);
+        assert_eq!(source.descriptor().height, 16);
+        assert_eq!(source.descriptor().pixfmt, PixelFormat::Jpeg);
+
+        let mut buf = Vec::new();
+        assert!(source.read(&mut buf).unwrap());
+        assert_eq!(buf, first);
+        assert!(source.read(&mut buf).unwrap());
+        assert_eq!(buf, second);
+        assert!(!source.read(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn y4m_header() {
+        let mut data = b"YUV4MPEG2 W4 H2 F30000:1001 Ip A1:1 C422 XYSCSS=422\nFRAME\n".to_vec();
+        data.extend(0..16u8);
+
+        let mut source = open("header.y4m", &data).unwrap();
+        assert_eq!(
+            *source.descriptor(),
+            Descriptor {
+                width: 4,
+                height: 2,
+                pixfmt: PixelFormat::I422(16),
+                interval: Interval::new(1001, 30000),
+            }
+        );
+        assert_eq!(source.format().size, 16);
+
+        let mut buf = Vec::new();
+        assert!(source.read(&mut buf).unwrap());
+        assert_eq!(buf, (0..16u8).collect::<Vec<_>>());
+        assert!(!source.read(&mut buf).unwrap());
+
+        // the colorspace defaults to 4:2:0
+        let source = open("default.y4m", b"YUV4MPEG2 W2 H2\n").unwrap();
+        assert_eq!(source.descriptor().pixfmt, PixelFormat::I420(12));
+        assert_eq!(source.descriptor().interval, Interval::from_fps(30));
+    }
+
+    #[test]
+    fn y4m_header_errors() {
+        assert!(open("magic.y4m", b"YUV4MPEG W2 H2\n").is_err());
+        assert!(open("size.y4m", b"YUV4MPEG2 W2 C420\n").is_err());
+        let res = open("colorspace.y4m", b"YUV4MPEG2 W2 H2 C411\n");
+        assert!(matches!(res, Err(e) if e.kind() == ErrorKind::UnsupportedFormat));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_rgba() {
+        let mut data = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut data, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        }
+
+        assert_eq!(png_format(&data).unwrap(), (2, 1, PixelFormat::Rgba(32)));
+        let mut buf = Vec::new();
+        png_decode(&data, &mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
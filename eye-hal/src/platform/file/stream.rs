@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::platform::file::source::Source;
+use crate::platform::util::wait;
+use crate::stream::{Format, Frame, Interval};
+use crate::traits::Stream;
+
+pub struct Handle {
+    source: Source,
+    format: Format,
+    looping: bool,
+    realtime: bool,
+    start: Option<Instant>,
+    sequence: u64,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl Handle {
+    pub(crate) fn new(source: Source, interval: Interval, looping: bool, realtime: bool) -> Self {
+        let format = Format {
+            interval,
+            ..source.format()
+        };
+
+        Handle {
+            source,
+            format,
+            looping,
+            realtime,
+            start: None,
+            sequence: 0,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Reads the next frame into the buffer, returns false once the stream is over
+    fn read(&mut self) -> Result<bool> {
+        if self.source.read(&mut self.buf)? {
+            return Ok(true);
+        }
+
+        if !self.looping {
+            return Ok(false);
+        }
+
+        // an empty source would never yield a frame, so only attempt to rewind once
+        self.source.rewind()?;
+        self.source.read(&mut self.buf)
+    }
+
+    fn play(&mut self, timeout: Option<Duration>) -> Option<Result<Frame<'_>>> {
+        if self.done {
+            return None;
+        }
+
+        let sequence = self.sequence;
+
+        // Timestamps describe the position in the recording, so they are independent of the
+        // actual playback speed.
+        let timestamp = self.format.interval.elapsed(sequence);
+
+        if self.realtime {
+            let start = *self.start.get_or_insert_with(Instant::now);
+            if let Err(e) = wait(start + timestamp, timeout) {
+                return Some(Err(e));
+            }
+        }
+
+        match self.read() {
+            Ok(true) => {}
+            Ok(false) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => return Some(Err(e)),
+        }
+        self.sequence += 1;
+
+        let frame = Frame::new(&self.buf[..])
+            .timestamp(timestamp)
+            .sequence(sequence);
+        Some(Ok(frame))
+    }
+}
+
+impl<'a> Stream<'a> for Handle {
+    type Item = Result<Frame<'a>>;
+
+    fn format(&self) -> Format {
+        self.format.clone()
+    }
+
+    fn next(&'a mut self) -> Option<Self::Item> {
+        self.play(None)
+    }
+
+    fn next_timeout(&'a mut self, timeout: Duration) -> Option<Self::Item> {
+        self.play(Some(timeout))
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Mutex;
+
+use crate::device;
+use crate::error::{Error, ErrorKind, Result};
+use crate::platform::mock::device::{Config, Handle as DeviceHandle};
+use crate::traits::Context as ContextTrait;
+
+#[derive(Debug)]
+/// Runtime context
+///
+/// Holds the configuration of all virtual devices. The default context contains a single device
+/// which resembles a common webcam (see [`Config::default`]).
+///
+/// Devices can be plugged and unplugged at runtime to simulate hotplug events.
+pub struct Context {
+    /// Unplugged devices leave a hole, so the URIs of the remaining devices stay valid
+    devices: Vec<Option<Config>>,
+    watchers: Mutex<Vec<device::Notifier>>,
+}
+
+impl Context {
+    /// Returns a context without any devices
+    pub fn new() -> Self {
+        Context {
+            devices: Vec::new(),
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Virtual device configuration
+    pub fn device(mut self, config: Config) -> Self {
+        self.devices.push(Some(config));
+        self
+    }
+
+    /// Adds a device and notifies all watchers
+    ///
+    /// Returns the URI of the new device.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Virtual device configuration
+    pub fn plug(&mut self, config: Config) -> String {
+        let desc = describe(self.devices.len(), &config);
+        self.devices.push(Some(config));
+
+        let uri = desc.uri.clone();
+        self.notify(device::Event::Added(desc));
+        uri
+    }
+
+    /// Removes a device and notifies all watchers
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - URI of the device
+    pub fn unplug(&mut self, uri: &str) -> Result<()> {
+        let index = parse_uri(uri)?;
+        match self.devices.get_mut(index).and_then(Option::take) {
+            Some(_) => {
+                self.notify(device::Event::Removed(uri.to_string()));
+                Ok(())
+            }
+            None => Err(Error::new(ErrorKind::NotFound, "no such device")),
+        }
+    }
+
+    fn notify(&self, event: device::Event) {
+        let mut watchers = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+        // forget about watchers which were dropped
+        watchers.retain(|watcher| watcher.notify(event.clone()));
+    }
+}
+
+impl Clone for Context {
+    /// Returns a context with the same devices, but without any watchers
+    fn clone(&self) -> Self {
+        Context {
+            devices: self.devices.clone(),
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::new().device(Config::default())
+    }
+}
+
+impl<'a> ContextTrait<'a> for Context {
+    type Device = DeviceHandle;
+
+    fn devices(&self) -> Result<Vec<device::Description>> {
+        let devices = self
+            .devices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, config)| config.as_ref().map(|config| describe(i, config)))
+            .collect();
+
+        Ok(devices)
+    }
+
+    fn open_device(&self, uri: &str) -> Result<Self::Device> {
+        let index = parse_uri(uri)?;
+        match self.devices.get(index) {
+            Some(Some(config)) => Ok(DeviceHandle::new(config.clone())),
+            _ => Err(Error::new(ErrorKind::NotFound, "no such device")),
+        }
+    }
+
+    fn watch(&self) -> Result<device::Watcher> {
+        let (watcher, notifier) = device::Watcher::new();
+        self.watchers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(notifier);
+
+        Ok(watcher)
+    }
+}
+
+fn describe(index: usize, config: &Config) -> device::Description {
+    device::Description {
+        uri: format!("mock://{}", index),
+        product: config.product.clone(),
+        backend: "mock".to_string(),
+        ..Default::default()
+    }
+}
+
+fn parse_uri(uri: &str) -> Result<usize> {
+    let index = if let Some(index) = uri.strip_prefix("mock://") {
+        index
+    } else {
+        return Err(Error::new(ErrorKind::InvalidArgument, "invalid URI"));
+    };
+
+    match index.parse::<usize>() {
+        Ok(index) => Ok(index),
+        Err(_) => Err(Error::new(
+            ErrorKind::InvalidArgument,
+            "malformed index in URI",
+        )),
+    }
+}
@@ -0,0 +1,419 @@
+use std::sync::{Arc, Mutex};
+
+use crate::control;
+use crate::error::{Error, ErrorKind, Result};
+use crate::format::PixelFormat;
+use crate::platform::mock::pattern::Pattern;
+use crate::platform::mock::stream::{self as mock_stream, Handle as StreamHandle};
+use crate::stream;
+use crate::traits::Device;
+
+#[derive(Clone, Debug)]
+/// Virtual device configuration
+pub struct Config {
+    /// Human-readable product name
+    pub product: String,
+    /// Supported streams
+    pub streams: Vec<stream::Descriptor>,
+    /// Supported ranges of streams, e.g. to emulate capture cards with arbitrary frame sizes
+    pub ranges: Vec<stream::Range>,
+    /// Supported controls along with their initial state
+    pub controls: Vec<(control::Descriptor, control::State)>,
+    /// Image content of all streams
+    pub pattern: Pattern,
+    /// Whether to draw the frame sequence number into the top left corner of each frame
+    pub counter: bool,
+    /// Whether to generate frames in real time (according to the stream interval) instead of as
+    /// fast as possible
+    pub realtime: bool,
+}
+
+impl Config {
+    /// Returns a device configuration without any streams or controls
+    ///
+    /// # Arguments
+    ///
+    /// * `product` - Human-readable product name
+    pub fn new<S: Into<String>>(product: S) -> Self {
+        Config {
+            product: product.into(),
+            streams: Vec::new(),
+            ranges: Vec::new(),
+            controls: Vec::new(),
+            pattern: Pattern::default(),
+            counter: true,
+            realtime: false,
+        }
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `desc` - Stream descriptor
+    pub fn stream(mut self, desc: stream::Descriptor) -> Self {
+        self.streams.push(desc);
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Range of streams
+    pub fn range(mut self, range: stream::Range) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `desc` - Control descriptor
+    /// * `state` - Initial control state
+    pub fn control(mut self, desc: control::Descriptor, state: control::State) -> Self {
+        self.controls.push((desc, state));
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Image content
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `counter` - Whether to draw the frame sequence number
+    pub fn counter(mut self, counter: bool) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `realtime` - Whether to pace frame generation according to the stream interval
+    pub fn realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+}
+
+impl Default for Config {
+    /// Returns the configuration of a common webcam
+    ///
+    /// Like most real webcams, the device offers YUYV and MJPEG streams only, so RGB output
+    /// requires conversion.
+    fn default() -> Self {
+        let interval = stream::Interval::from_fps(30);
+        let mut config = Config::new("Mock Camera");
+
+        for (width, height) in [(640, 480), (1280, 720)] {
+            for pixfmt in [PixelFormat::Yuyv(16), PixelFormat::Jpeg] {
+                config = config.stream(stream::Descriptor {
+                    width,
+                    height,
+                    pixfmt,
+                    interval,
+                });
+            }
+        }
+
+        let rw = control::Flags::READ | control::Flags::WRITE;
+        config
+            .control(
+                control::Descriptor {
+                    id: 1,
+                    name: String::from("Brightness"),
+                    kind: Some(control::Kind::Brightness),
+                    typ: control::Type::Number {
+                        range: (0.0, 255.0),
+                        step: 1.0,
+                    },
+                    flags: rw,
+                    default: Some(control::State::Number(128.0)),
+                    value: None,
+                },
+                control::State::Number(128.0),
+            )
+            .control(
+                control::Descriptor {
+                    id: 2,
+                    name: String::from("Contrast"),
+                    kind: Some(control::Kind::Contrast),
+                    typ: control::Type::Number {
+                        range: (0.0, 255.0),
+                        step: 1.0,
+                    },
+                    flags: rw,
+                    default: Some(control::State::Number(32.0)),
+                    value: None,
+                },
+                control::State::Number(32.0),
+            )
+            .control(
+                control::Descriptor {
+                    id: 3,
+                    name: String::from("White Balance, Automatic"),
+                    kind: Some(control::Kind::AutoWhiteBalance),
+                    typ: control::Type::Boolean,
+                    flags: rw,
+                    default: Some(control::State::Boolean(true)),
+                    value: None,
+                },
+                control::State::Boolean(true),
+            )
+            .control(
+                control::Descriptor {
+                    id: 4,
+                    name: String::from("Power Line Frequency"),
+                    kind: Some(control::Kind::PowerLineFrequency),
+                    typ: control::Type::Menu(vec![
+                        (0, control::MenuItem::String(String::from("Disabled"))),
+                        (1, control::MenuItem::String(String::from("50 Hz"))),
+                        (2, control::MenuItem::String(String::from("60 Hz"))),
+                    ]),
+                    flags: rw,
+                    default: Some(control::State::MenuIndex(1)),
+                    value: None,
+                },
+                control::State::MenuIndex(1),
+            )
+            .control(
+                control::Descriptor {
+                    id: 5,
+                    name: String::from("Auto Exposure"),
+                    kind: Some(control::Kind::AutoExposure),
+                    // sparse menu, just like the one of many UVC cameras
+                    typ: control::Type::Menu(vec![
+                        (1, control::MenuItem::String(String::from("Manual Mode"))),
+                        (
+                            3,
+                            control::MenuItem::String(String::from("Aperture Priority Mode")),
+                        ),
+                    ]),
+                    flags: rw,
+                    default: Some(control::State::MenuIndex(3)),
+                    value: None,
+                },
+                control::State::MenuIndex(3),
+            )
+            .control(
+                control::Descriptor {
+                    id: 6,
+                    name: String::from("Exposure Time, Absolute"),
+                    kind: Some(control::Kind::Exposure),
+                    typ: control::Type::Number {
+                        range: (3.0, 2047.0),
+                        step: 1.0,
+                    },
+                    flags: rw,
+                    default: Some(control::State::Number(250.0)),
+                    value: None,
+                },
+                control::State::Number(250.0),
+            )
+    }
+}
+
+pub struct Handle {
+    config: Config,
+    // shared with subscriptions, which poll the controls
+    controls: Arc<Mutex<Vec<(control::Descriptor, control::State)>>>,
+}
+
+impl Handle {
+    pub fn new(config: Config) -> Self {
+        let controls = Arc::new(Mutex::new(config.controls.clone()));
+        Handle { config, controls }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+}
+
+impl<'a> Device<'a> for Handle {
+    type Stream = StreamHandle;
+
+    fn streams(&self) -> Result<Vec<stream::Descriptor>> {
+        let mut streams = self.config.streams.clone();
+        for range in &self.config.ranges {
+            streams.extend(range.descriptors());
+        }
+
+        Ok(streams)
+    }
+
+    fn stream_ranges(&self) -> Result<Vec<stream::Range>> {
+        let mut ranges: Vec<stream::Range> = self
+            .config
+            .streams
+            .iter()
+            .map(stream::Range::from)
+            .collect();
+        ranges.extend(self.config.ranges.iter().cloned());
+
+        Ok(ranges)
+    }
+
+    fn start_stream(&self, desc: &stream::Descriptor) -> Result<Self::Stream> {
+        if !self.config.streams.contains(desc)
+            && !self.config.ranges.iter().any(|range| range.contains(desc))
+        {
+            return Err(Error::new(
+                ErrorKind::UnsupportedFormat,
+                "stream not supported by device",
+            ));
+        }
+
+        if !mock_stream::supported(desc) {
+            return Err(Error::new(
+                ErrorKind::UnsupportedFormat,
+                format!("cannot generate frames in {}", desc.pixfmt),
+            ));
+        }
+
+        Ok(StreamHandle::new(
+            desc.clone(),
+            self.config.pattern,
+            self.config.counter,
+            self.config.realtime,
+        ))
+    }
+
+    fn controls(&self) -> Result<Vec<control::Descriptor>> {
+        Ok(describe(&self.controls.lock().unwrap()))
+    }
+
+    fn control(&self, id: u32) -> Result<control::State> {
+        let controls = self.controls.lock().unwrap();
+        let (desc, state) = controls
+            .iter()
+            .find(|(desc, _)| desc.id == id)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no such control"))?;
+
+        if !desc.readable() {
+            return Err(Error::new(ErrorKind::Other, "control is not readable"));
+        }
+
+        Ok(state.clone())
+    }
+
+    fn set_control(&mut self, id: u32, val: &control::State) -> Result<()> {
+        let mut controls = self.controls.lock().unwrap();
+        let (desc, state) = controls
+            .iter_mut()
+            .find(|(desc, _)| desc.id == id)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no such control"))?;
+
+        if !desc.writable() {
+            return Err(Error::new(ErrorKind::Other, "control is not writable"));
+        }
+
+        let valid = match (&desc.typ, val) {
+            (control::Type::Stateless, control::State::None) => true,
+            (control::Type::Boolean, control::State::Boolean(_)) => true,
+            (control::Type::Number { range, .. }, control::State::Number(val)) => {
+                *val >= range.0 && *val <= range.1
+            }
+            (control::Type::Number { range, .. }, control::State::Integer64(val)) => {
+                *val as f64 >= range.0 && *val as f64 <= range.1
+            }
+            (control::Type::String, control::State::String(_)) => true,
+            (control::Type::Bitmask, control::State::Number(val)) => *val >= 0.0,
+            (control::Type::Bitmask, control::State::Bitmask(_)) => true,
+            (control::Type::Menu(items), control::State::MenuIndex(index)) => {
+                items.iter().any(|(i, _)| i == index)
+            }
+            (control::Type::Compound, control::State::Array(_) | control::State::Compound(_)) => {
+                true
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(Error::new(
+                ErrorKind::InvalidArgument,
+                "invalid control state",
+            ));
+        }
+
+        // stateless controls (e.g. buttons) do not store any value
+        if let control::Type::Stateless = desc.typ {
+            return Ok(());
+        }
+
+        *state = val.clone();
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Result<control::Subscription<'a>> {
+        let controls = self.controls.clone();
+        control::Subscription::poll(move || Ok(describe(&controls.lock().unwrap())))
+    }
+}
+
+/// Returns the descriptors of controls along with their current values
+///
+/// Like on real devices, manual exposure is inactive unless auto exposure is in manual mode.
+fn describe(controls: &[(control::Descriptor, control::State)]) -> Vec<control::Descriptor> {
+    let auto_exposure = controls.iter().any(|(desc, state)| {
+        desc.kind == Some(control::Kind::AutoExposure) && *state != control::State::MenuIndex(1)
+    });
+
+    controls
+        .iter()
+        .map(|(desc, state)| {
+            let mut flags = desc.flags;
+            if auto_exposure && desc.kind == Some(control::Kind::Exposure) {
+                flags.insert(control::Flags::INACTIVE);
+            }
+
+            control::Descriptor {
+                flags,
+                value: if desc.readable() {
+                    Some(state.clone())
+                } else {
+                    None
+                },
+                ..desc.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inactive_exposure() {
+        let mut dev = Handle::new(Config::default());
+        let exposure = |dev: &Handle| {
+            dev.controls()
+                .unwrap()
+                .into_iter()
+                .find(|desc| desc.kind == Some(control::Kind::Exposure))
+                .unwrap()
+        };
+
+        // auto exposure is on by default, manual exposure can still be read
+        let desc = exposure(&dev);
+        assert!(!desc.active());
+        assert!(desc.readable() && desc.writable());
+        assert_eq!(desc.value, Some(control::State::Number(250.0)));
+
+        dev.set_control_by_kind(control::Kind::AutoExposure, &control::State::MenuIndex(1))
+            .unwrap();
+        assert!(exposure(&dev).active());
+    }
+}
@@ -0,0 +1,337 @@
+//! Minimal baseline JPEG encoder
+//!
+//! Produces YCbCr 4:4:4 images using the example quantization and Huffman tables of the JPEG
+//! specification (ITU T.81, Annex K). Image quality and compression ratio do not matter for mock
+//! devices, we just need valid bitstreams which any decoder can handle.
+
+use std::f32::consts::PI;
+
+/// Maps zigzag order to natural (row major) order
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+const LUMA_QUANT: [u8; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, //
+    12, 12, 14, 19, 26, 58, 60, 55, //
+    14, 13, 16, 24, 40, 57, 69, 56, //
+    14, 17, 22, 29, 51, 87, 80, 62, //
+    18, 22, 37, 56, 68, 109, 103, 77, //
+    24, 35, 55, 64, 81, 104, 113, 92, //
+    49, 64, 78, 87, 103, 121, 120, 101, //
+    72, 92, 95, 98, 112, 100, 103, 99, //
+];
+
+const CHROMA_QUANT: [u8; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, //
+    18, 21, 26, 66, 99, 99, 99, 99, //
+    24, 26, 56, 99, 99, 99, 99, 99, //
+    47, 66, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+];
+
+const DC_LUMA_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const DC_CHROMA_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALS: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+const AC_LUMA_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+const AC_LUMA_VALS: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+    0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+    0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+const AC_CHROMA_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+const AC_CHROMA_VALS: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+    0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+    0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+    0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+    0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+    0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+/// Huffman code (code, length) for each symbol
+type HuffTable = [(u16, u8); 256];
+
+fn huff_table(bits: &[u8; 16], vals: &[u8]) -> HuffTable {
+    let mut table = [(0, 0); 256];
+    let mut code = 0u16;
+    let mut k = 0;
+    for (i, n) in bits.iter().enumerate() {
+        for _ in 0..*n {
+            table[vals[k] as usize] = (code, i as u8 + 1);
+            code += 1;
+            k += 1;
+        }
+        code <<= 1;
+    }
+    table
+}
+
+struct BitWriter<'a> {
+    out: &'a mut Vec<u8>,
+    acc: u32,
+    len: u32,
+}
+
+impl<'a> BitWriter<'a> {
+    fn write(&mut self, bits: u16, len: u8) {
+        let len = len as u32;
+        self.acc = (self.acc << len) | (bits as u32 & ((1 << len) - 1));
+        self.len += len;
+
+        while self.len >= 8 {
+            let byte = (self.acc >> (self.len - 8)) as u8;
+            self.out.push(byte);
+            // byte stuffing: 0xFF in the entropy coded segment must be followed by 0x00
+            if byte == 0xff {
+                self.out.push(0x00);
+            }
+            self.len -= 8;
+        }
+        self.acc &= (1 << self.len) - 1;
+    }
+
+    fn flush(&mut self) {
+        if self.len > 0 {
+            // pad the last byte with ones
+            let pad = 8 - self.len;
+            self.write((1 << pad) - 1, pad as u8);
+        }
+    }
+}
+
+/// Returns the magnitude category and the amplitude bits of a coefficient
+fn magnitude(val: i32) -> (u8, u16) {
+    let size = 32 - val.unsigned_abs().leading_zeros();
+    let bits = if val < 0 { val + (1 << size) - 1 } else { val };
+    (size as u8, bits as u16)
+}
+
+struct Encoder<'a> {
+    writer: BitWriter<'a>,
+    cos: [[f32; 8]; 8],
+}
+
+impl<'a> Encoder<'a> {
+    fn block(
+        &mut self,
+        block: &[f32; 64],
+        quant: &[u8; 64],
+        pred: &mut i32,
+        dc: &HuffTable,
+        ac: &HuffTable,
+    ) {
+        // separable forward DCT: rows first, then columns
+        let mut rows = [0f32; 64];
+        for y in 0..8 {
+            for u in 0..8 {
+                rows[y * 8 + u] = (0..8).map(|x| self.cos[u][x] * block[y * 8 + x]).sum();
+            }
+        }
+        let mut coeffs = [0i32; 64];
+        for (k, i) in ZIGZAG.iter().enumerate() {
+            let (v, u) = (i / 8, i % 8);
+            let val: f32 = (0..8).map(|y| self.cos[v][y] * rows[y * 8 + u]).sum();
+            coeffs[k] = (val / quant[*i] as f32).round() as i32;
+        }
+
+        let (size, bits) = magnitude(coeffs[0] - *pred);
+        *pred = coeffs[0];
+        let (code, len) = dc[size as usize];
+        self.writer.write(code, len);
+        self.writer.write(bits, size);
+
+        let mut run = 0;
+        for coeff in &coeffs[1..] {
+            if *coeff == 0 {
+                run += 1;
+                continue;
+            }
+
+            while run > 15 {
+                // ZRL: sixteen zeros
+                let (code, len) = ac[0xf0];
+                self.writer.write(code, len);
+                run -= 16;
+            }
+
+            let (size, bits) = magnitude(*coeff);
+            let (code, len) = ac[(run << 4) | size as usize];
+            self.writer.write(code, len);
+            self.writer.write(bits, size);
+            run = 0;
+        }
+        if run > 0 {
+            // EOB: all remaining coefficients are zero
+            let (code, len) = ac[0x00];
+            self.writer.write(code, len);
+        }
+    }
+}
+
+/// Encodes a packed RGB24 buffer as JPEG
+pub(crate) fn encode(rgb: &[u8], width: u32, height: u32, out: &mut Vec<u8>) {
+    // SOI, APP0 (JFIF)
+    out.extend_from_slice(&[0xff, 0xd8]);
+    out.extend_from_slice(&[
+        0xff, 0xe0, 0x00, 0x10, b'J', b'F', b'I', b'F', 0x00, 0x01, 0x01, 0x00, 0x00, 0x01, 0x00,
+        0x01, 0x00, 0x00,
+    ]);
+
+    // DQT
+    out.extend_from_slice(&[0xff, 0xdb, 0x00, 0x84]);
+    for (id, quant) in [&LUMA_QUANT, &CHROMA_QUANT].iter().enumerate() {
+        out.push(id as u8);
+        out.extend(ZIGZAG.iter().map(|i| quant[*i]));
+    }
+
+    // SOF0
+    out.extend_from_slice(&[0xff, 0xc0, 0x00, 0x11, 0x08]);
+    out.extend_from_slice(&(height as u16).to_be_bytes());
+    out.extend_from_slice(&(width as u16).to_be_bytes());
+    out.extend_from_slice(&[0x03, 0x01, 0x11, 0x00, 0x02, 0x11, 0x01, 0x03, 0x11, 0x01]);
+
+    // DHT
+    let tables: [(u8, &[u8; 16], &[u8]); 4] = [
+        (0x00, &DC_LUMA_BITS, &DC_VALS),
+        (0x10, &AC_LUMA_BITS, &AC_LUMA_VALS),
+        (0x01, &DC_CHROMA_BITS, &DC_VALS),
+        (0x11, &AC_CHROMA_BITS, &AC_CHROMA_VALS),
+    ];
+    let len = 2 + tables
+        .iter()
+        .map(|(_, _, vals)| 17 + vals.len())
+        .sum::<usize>();
+    out.extend_from_slice(&[0xff, 0xc4]);
+    out.extend_from_slice(&(len as u16).to_be_bytes());
+    for (class, bits, vals) in &tables {
+        out.push(*class);
+        out.extend_from_slice(*bits);
+        out.extend_from_slice(vals);
+    }
+
+    // SOS
+    out.extend_from_slice(&[
+        0xff, 0xda, 0x00, 0x0c, 0x03, 0x01, 0x00, 0x02, 0x11, 0x03, 0x11, 0x00, 0x3f, 0x00,
+    ]);
+
+    let dc_luma = huff_table(&DC_LUMA_BITS, &DC_VALS);
+    let ac_luma = huff_table(&AC_LUMA_BITS, &AC_LUMA_VALS);
+    let dc_chroma = huff_table(&DC_CHROMA_BITS, &DC_VALS);
+    let ac_chroma = huff_table(&AC_CHROMA_BITS, &AC_CHROMA_VALS);
+
+    let mut cos = [[0f32; 8]; 8];
+    for (u, row) in cos.iter_mut().enumerate() {
+        let c = if u == 0 { 1.0 / 2f32.sqrt() } else { 1.0 };
+        for (x, val) in row.iter_mut().enumerate() {
+            *val = c / 2.0 * ((2 * x + 1) as f32 * u as f32 * PI / 16.0).cos();
+        }
+    }
+
+    let mut encoder = Encoder {
+        writer: BitWriter {
+            out,
+            acc: 0,
+            len: 0,
+        },
+        cos,
+    };
+
+    let (width, height) = (width as usize, height as usize);
+    let mut preds = [0i32; 3];
+    let mut blocks = [[0f32; 64]; 3];
+    for by in (0..height).step_by(8) {
+        for bx in (0..width).step_by(8) {
+            let [luma, cb, cr] = &mut blocks;
+            let samples = luma.iter_mut().zip(cb.iter_mut()).zip(cr.iter_mut());
+            for (i, ((luma, cb), cr)) in samples.enumerate() {
+                // replicate the edge pixels for partial blocks
+                let x = (bx + i % 8).min(width - 1);
+                let y = (by + i / 8).min(height - 1);
+                let px = &rgb[(y * width + x) * 3..(y * width + x) * 3 + 3];
+                let (r, g, b) = (px[0] as f32, px[1] as f32, px[2] as f32);
+
+                // level shifted JFIF YCbCr
+                *luma = 0.299 * r + 0.587 * g + 0.114 * b - 128.0;
+                *cb = -0.168_736 * r - 0.331_264 * g + 0.5 * b;
+                *cr = 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+            }
+
+            encoder.block(&blocks[0], &LUMA_QUANT, &mut preds[0], &dc_luma, &ac_luma);
+            encoder.block(
+                &blocks[1],
+                &CHROMA_QUANT,
+                &mut preds[1],
+                &dc_chroma,
+                &ac_chroma,
+            );
+            encoder.block(
+                &blocks[2],
+                &CHROMA_QUANT,
+                &mut preds[2],
+                &dc_chroma,
+                &ac_chroma,
+            );
+        }
+    }
+    encoder.writer.flush();
+
+    // EOI
+    out.extend_from_slice(&[0xff, 0xd9]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{GenericImageView, ImageFormat};
+
+    #[test]
+    fn decode() {
+        // partial blocks at the right and bottom edges, one color per half
+        let (width, height) = (20, 12);
+        let mut rgb = Vec::new();
+        for _ in 0..height {
+            for x in 0..width {
+                rgb.extend_from_slice(if x < 10 {
+                    &[200, 40, 40]
+                } else {
+                    &[40, 40, 200]
+                });
+            }
+        }
+
+        let mut out = Vec::new();
+        encode(&rgb, width, height, &mut out);
+        assert!(out.starts_with(&[0xff, 0xd8]) && out.ends_with(&[0xff, 0xd9]));
+
+        let img = image::load_from_memory_with_format(&out, ImageFormat::Jpeg).unwrap();
+        assert_eq!(img.dimensions(), (width, height));
+        for (x, expected) in [(2, [200, 40, 40]), (17, [40, 40, 200])] {
+            let px = img.get_pixel(x, height - 1);
+            for (val, expected) in px.0.iter().zip(&expected) {
+                assert!((*val as i32 - expected).abs() < 16, "{:?}", px);
+            }
+        }
+    }
+}
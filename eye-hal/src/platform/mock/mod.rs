@@ -0,0 +1,47 @@
+//! Mock (virtual camera) backend
+//!
+//! Provides virtual devices which do not require any hardware. The devices, their streams and
+//! controls are fully configurable and every stream produces deterministic test patterns. This is
+//! mainly useful for testing code that sits on top of the HAL, e.g. in CI environments without
+//! any cameras attached.
+//!
+//! Devices are addressed by their index: the first configured device has the URI `mock://0`.
+//!
+//! # Example
+//!
+//! ```
+//! use eye_hal::format::PixelFormat;
+//! use eye_hal::platform::mock;
+//! use eye_hal::platform::Context;
+//! use eye_hal::stream::{Descriptor, Interval};
+//! use eye_hal::traits::{Context as _, Device as _, Stream as _};
+//!
+//! let config = mock::Config::new("Virtual Camera")
+//!     .stream(Descriptor {
+//!         width: 320,
+//!         height: 240,
+//!         pixfmt: PixelFormat::Rgb(24),
+//!         interval: Interval::from_fps(30),
+//!     })
+//!     .pattern(mock::Pattern::MovingBox);
+//! let ctx = Context::Mock(mock::Context::new().device(config));
+//!
+//! let dev = ctx.open_device("mock://0").unwrap();
+//! let desc = dev.streams().unwrap()[0].clone();
+//! let mut stream = dev.start_stream(&desc).unwrap();
+//!
+//! let frame = stream.next().unwrap().unwrap();
+//! assert_eq!(frame.len(), 320 * 240 * 3);
+//! assert_eq!(frame.sequence, 0);
+//! ```
+
+pub mod context;
+pub mod device;
+pub mod stream;
+
+mod jpeg;
+mod pattern;
+
+pub use context::Context;
+pub use device::Config;
+pub use pattern::Pattern;
@@ -0,0 +1,114 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Test pattern drawn by virtual devices
+///
+/// All patterns are a pure function of the frame size and sequence number, so every run of a
+/// stream yields exactly the same frames.
+pub enum Pattern {
+    /// Eight vertical bars (white, yellow, cyan, green, magenta, red, blue, black)
+    #[default]
+    ColorBars,
+    /// Horizontal red and vertical green gradients, the blue component changes with each frame
+    Gradient,
+    /// White square moving across a gray background
+    MovingBox,
+}
+
+const BARS: [[u8; 3]; 8] = [
+    [255, 255, 255],
+    [255, 255, 0],
+    [0, 255, 255],
+    [0, 255, 0],
+    [255, 0, 255],
+    [255, 0, 0],
+    [0, 0, 255],
+    [0, 0, 0],
+];
+
+/// 3x5 pixel glyphs for the digits 0-9, one bit per pixel, row by row starting at the MSB
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+/// Renders a pattern into a packed RGB24 buffer
+pub(crate) fn render(pattern: Pattern, width: u32, height: u32, sequence: u64, buf: &mut Vec<u8>) {
+    let (width, height) = (width as usize, height as usize);
+    buf.resize(width * height * 3, 0);
+
+    match pattern {
+        Pattern::ColorBars => {
+            for (i, px) in buf.chunks_exact_mut(3).enumerate() {
+                let x = i % width;
+                px.copy_from_slice(&BARS[x * BARS.len() / width]);
+            }
+        }
+        Pattern::Gradient => {
+            let blue = (sequence % 256) as u8;
+            for (i, px) in buf.chunks_exact_mut(3).enumerate() {
+                let (x, y) = (i % width, i / width);
+                px[0] = (x * 255 / (width - 1).max(1)) as u8;
+                px[1] = (y * 255 / (height - 1).max(1)) as u8;
+                px[2] = blue;
+            }
+        }
+        Pattern::MovingBox => {
+            buf.iter_mut().for_each(|b| *b = 64);
+
+            let size = (width.min(height) / 4).max(1);
+            let x0 = (sequence.wrapping_mul(4) % (width - size + 1) as u64) as usize;
+            let y0 = (sequence.wrapping_mul(2) % (height - size + 1) as u64) as usize;
+            fill(buf, width, x0, y0, size, size, [255, 255, 255]);
+        }
+    }
+}
+
+/// Draws the frame sequence number into the top left corner of a packed RGB24 buffer
+pub(crate) fn counter(width: u32, height: u32, sequence: u64, buf: &mut [u8]) {
+    let (width, height) = (width as usize, height as usize);
+    let digits: Vec<usize> = sequence
+        .to_string()
+        .bytes()
+        .map(|c| (c - b'0') as usize)
+        .collect();
+
+    // Each glyph is 3x5 pixels with a gap of one pixel in between glyphs. A border of one pixel
+    // surrounds the whole text.
+    let scale = (height / 96).max(1);
+    let box_width = (digits.len() * 4 + 1) * scale;
+    let box_height = 7 * scale;
+    if box_width > width || box_height > height {
+        return;
+    }
+
+    fill(buf, width, 0, 0, box_width, box_height, [0, 0, 0]);
+    for (i, digit) in digits.into_iter().enumerate() {
+        let glyph = DIGITS[digit];
+        for row in 0..5 {
+            for col in 0..3 {
+                if glyph & (1 << (14 - (row * 3 + col))) != 0 {
+                    let x = (1 + i * 4 + col) * scale;
+                    let y = (1 + row) * scale;
+                    fill(buf, width, x, y, scale, scale, [255, 255, 255]);
+                }
+            }
+        }
+    }
+}
+
+fn fill(buf: &mut [u8], width: usize, x: usize, y: usize, w: usize, h: usize, color: [u8; 3]) {
+    for row in y..y + h {
+        let start = (row * width + x) * 3;
+        let end = start + w * 3;
+        buf[start..end]
+            .chunks_exact_mut(3)
+            .for_each(|px| px.copy_from_slice(&color));
+    }
+}
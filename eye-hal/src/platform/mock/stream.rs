@@ -0,0 +1,342 @@
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::format::{BayerPattern, ImageFormat, PixelFormat};
+use crate::platform::mock::{jpeg, pattern, pattern::Pattern};
+use crate::platform::util::wait;
+use crate::stream::{Descriptor, Format, Frame};
+use crate::traits::Stream;
+
+pub struct Handle {
+    desc: Descriptor,
+    pattern: Pattern,
+    counter: bool,
+    realtime: bool,
+    start: Option<Instant>,
+    sequence: u64,
+    rgb: Vec<u8>,
+    buf: Vec<u8>,
+}
+
+impl Handle {
+    pub fn new(desc: Descriptor, pattern: Pattern, counter: bool, realtime: bool) -> Self {
+        Handle {
+            desc,
+            pattern,
+            counter,
+            realtime,
+            start: None,
+            sequence: 0,
+            rgb: Vec::new(),
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl Handle {
+    fn capture(&mut self, timeout: Option<Duration>) -> Option<Result<Frame<'_>>> {
+        let sequence = self.sequence;
+
+        // Timestamps are derived from the sequence number so they are deterministic, even when
+        // frames are generated faster than real time.
+        let timestamp = self.desc.interval.elapsed(sequence);
+
+        if self.realtime {
+            let start = *self.start.get_or_insert_with(Instant::now);
+            if let Err(e) = wait(start + timestamp, timeout) {
+                return Some(Err(e));
+            }
+        }
+        self.sequence += 1;
+
+        let (width, height) = (self.desc.width, self.desc.height);
+        pattern::render(self.pattern, width, height, sequence, &mut self.rgb);
+        if self.counter {
+            pattern::counter(width, height, sequence, &mut self.rgb);
+        }
+        convert(&self.rgb, width, height, &self.desc.pixfmt, &mut self.buf);
+
+        let frame = Frame::new(&self.buf[..])
+            .timestamp(timestamp)
+            .sequence(sequence);
+        Some(Ok(frame))
+    }
+}
+
+impl<'a> Stream<'a> for Handle {
+    type Item = Result<Frame<'a>>;
+
+    fn format(&self) -> Format {
+        // frames are generated in exactly the requested format
+        Format::packed(&self.desc)
+    }
+
+    fn next(&'a mut self) -> Option<Self::Item> {
+        self.capture(None)
+    }
+
+    fn next_timeout(&'a mut self, timeout: Duration) -> Option<Self::Item> {
+        self.capture(Some(timeout))
+    }
+}
+
+/// Returns true if frames can be generated for a stream
+pub(crate) fn supported(desc: &Descriptor) -> bool {
+    if desc.width == 0 || desc.height == 0 {
+        return false;
+    }
+
+    match &desc.pixfmt {
+        PixelFormat::Rgb(24) | PixelFormat::Rgb(32) | PixelFormat::Rgba(32) => true,
+        PixelFormat::Bgr(24) | PixelFormat::Bgr(32) | PixelFormat::Bgra(32) => true,
+        PixelFormat::Gray(8) | PixelFormat::Gray(16) | PixelFormat::Depth(16) => true,
+        // baseline JPEG limits the image dimensions to 16 bits
+        PixelFormat::Jpeg => desc.width <= u16::MAX as u32 && desc.height <= u16::MAX as u32,
+        // packed 4:2:2 formats store two pixels per macropixel
+        PixelFormat::Yuyv(16)
+        | PixelFormat::Uyvy(16)
+        | PixelFormat::Yvyu(16)
+        | PixelFormat::Vyuy(16) => desc.width & 1 == 0,
+        // odd dimensions are fine here, chroma planes are rounded up to whole samples
+        PixelFormat::Nv12(12) | PixelFormat::Nv21(12) | PixelFormat::Nv16(16) => true,
+        PixelFormat::Nv61(16) | PixelFormat::I420(12) | PixelFormat::Yv12(12) => true,
+        PixelFormat::I422(16) | PixelFormat::I444(24) | PixelFormat::Uyv(24) => true,
+        PixelFormat::Bayer(_, 8) => true,
+        _ => false,
+    }
+}
+
+/// Converts a packed RGB24 buffer into the stream format
+fn convert(rgb: &[u8], width: u32, height: u32, pixfmt: &PixelFormat, out: &mut Vec<u8>) {
+    out.clear();
+
+    match pixfmt {
+        PixelFormat::Rgb(24) => out.extend_from_slice(rgb),
+        PixelFormat::Rgb(32) | PixelFormat::Rgba(32) => rgb
+            .chunks_exact(3)
+            .for_each(|px| out.extend_from_slice(&[px[0], px[1], px[2], 255])),
+        PixelFormat::Bgr(24) => rgb
+            .chunks_exact(3)
+            .for_each(|px| out.extend_from_slice(&[px[2], px[1], px[0]])),
+        PixelFormat::Bgr(32) | PixelFormat::Bgra(32) => rgb
+            .chunks_exact(3)
+            .for_each(|px| out.extend_from_slice(&[px[2], px[1], px[0], 255])),
+        PixelFormat::Gray(8) => rgb.chunks_exact(3).for_each(|px| out.push(luma(px))),
+        PixelFormat::Gray(16) | PixelFormat::Depth(16) => rgb
+            .chunks_exact(3)
+            .for_each(|px| out.extend_from_slice(&(luma(px) as u16 * 257).to_le_bytes())),
+        PixelFormat::Jpeg => jpeg::encode(rgb, width, height, out),
+        PixelFormat::Yuyv(_)
+        | PixelFormat::Uyvy(_)
+        | PixelFormat::Yvyu(_)
+        | PixelFormat::Vyuy(_) => {
+            rgb.chunks_exact(6).for_each(|px| {
+                let (y0, u0, v0) = yuv(&px[0..3]);
+                let (y1, u1, v1) = yuv(&px[3..6]);
+                let u = ((u0 as u16 + u1 as u16) / 2) as u8;
+                let v = ((v0 as u16 + v1 as u16) / 2) as u8;
+                let macropixel = match pixfmt {
+                    PixelFormat::Uyvy(_) => [u, y0, v, y1],
+                    PixelFormat::Yvyu(_) => [y0, v, y1, u],
+                    PixelFormat::Vyuy(_) => [v, y0, u, y1],
+                    _ => [y0, u, y1, v],
+                };
+                out.extend_from_slice(&macropixel);
+            });
+        }
+        PixelFormat::Uyv(_) => rgb.chunks_exact(3).for_each(|px| {
+            let (y, u, v) = yuv(px);
+            out.extend_from_slice(&[u, y, v]);
+        }),
+        PixelFormat::Bayer(pattern, _) => {
+            // component index (red, green, blue) of the top left 2x2 pixels
+            let cfa = match pattern {
+                BayerPattern::Bggr => [[2, 1], [1, 0]],
+                BayerPattern::Gbrg => [[1, 2], [0, 1]],
+                BayerPattern::Grbg => [[1, 0], [2, 1]],
+                BayerPattern::Rggb => [[0, 1], [1, 2]],
+            };
+            rgb.chunks_exact(3).enumerate().for_each(|(i, px)| {
+                let (x, y) = (i % width as usize, i / width as usize);
+                out.push(px[cfa[y & 1][x & 1]]);
+            });
+        }
+        PixelFormat::Nv12(_)
+        | PixelFormat::Nv21(_)
+        | PixelFormat::Nv16(_)
+        | PixelFormat::Nv61(_)
+        | PixelFormat::I420(_)
+        | PixelFormat::Yv12(_)
+        | PixelFormat::I422(_)
+        | PixelFormat::I444(_) => planar(rgb, width, height, pixfmt, out),
+        _ => unreachable!("unsupported formats are rejected when the stream is started"),
+    }
+}
+
+/// Converts a packed RGB24 buffer into a planar or semi-planar YUV format
+fn planar(rgb: &[u8], width: u32, height: u32, pixfmt: &PixelFormat, out: &mut Vec<u8>) {
+    let image = ImageFormat::new(width, height, pixfmt.clone());
+    out.resize(image.size().unwrap_or(0), 0);
+
+    // chroma subsampling in both directions and whether V is stored before U
+    let (sub_x, sub_y, swap) = match pixfmt {
+        PixelFormat::Nv12(_) | PixelFormat::I420(_) => (2, 2, false),
+        PixelFormat::Nv21(_) | PixelFormat::Yv12(_) => (2, 2, true),
+        PixelFormat::Nv16(_) | PixelFormat::I422(_) => (2, 1, false),
+        PixelFormat::Nv61(_) => (2, 1, true),
+        _ => (1, 1, false),
+    };
+
+    let (width, height) = (width as usize, height as usize);
+    let luma = &image.planes[0];
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) * 3;
+            out[luma.offset + y * luma.stride + x] = yuv(&rgb[i..i + 3]).0;
+        }
+    }
+
+    for y in 0..height.div_ceil(sub_y) {
+        for x in 0..width.div_ceil(sub_x) {
+            // average the chroma samples of all pixels in the block, which is cropped at the
+            // right and bottom edges for odd dimensions
+            let (mut u, mut v, mut n) = (0u32, 0u32, 0u32);
+            for py in y * sub_y..((y + 1) * sub_y).min(height) {
+                for px in x * sub_x..((x + 1) * sub_x).min(width) {
+                    let i = (py * width + px) * 3;
+                    let (_, pu, pv) = yuv(&rgb[i..i + 3]);
+                    u += pu as u32;
+                    v += pv as u32;
+                    n += 1;
+                }
+            }
+            let (u, v) = ((u / n) as u8, (v / n) as u8);
+            let (first, second) = if swap { (v, u) } else { (u, v) };
+
+            match &image.planes[1..] {
+                [chroma] => {
+                    let i = chroma.offset + y * chroma.stride + x * 2;
+                    out[i] = first;
+                    out[i + 1] = second;
+                }
+                [cb, cr] => {
+                    out[cb.offset + y * cb.stride + x] = first;
+                    out[cr.offset + y * cr.stride + x] = second;
+                }
+                _ => unreachable!("YUV formats have one or two chroma planes"),
+            }
+        }
+    }
+}
+
+/// Full range luminance
+fn luma(px: &[u8]) -> u8 {
+    let (r, g, b) = (px[0] as u32, px[1] as u32, px[2] as u32);
+    ((77 * r + 150 * g + 29 * b + 128) >> 8) as u8
+}
+
+/// Limited range YCbCr (BT.601)
+fn yuv(px: &[u8]) -> (u8, u8, u8) {
+    let (r, g, b) = (px[0] as i32, px[1] as i32, px[2] as i32);
+    let y = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    (y as u8, u as u8, v as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::stream::Interval;
+
+    const PLANAR: [PixelFormat; 11] = [
+        PixelFormat::Nv12(12),
+        PixelFormat::Nv21(12),
+        PixelFormat::Nv16(16),
+        PixelFormat::Nv61(16),
+        PixelFormat::I420(12),
+        PixelFormat::Yv12(12),
+        PixelFormat::I422(16),
+        PixelFormat::I444(24),
+        PixelFormat::Uyv(24),
+        PixelFormat::Bayer(BayerPattern::Bggr, 8),
+        PixelFormat::Bayer(BayerPattern::Rggb, 8),
+    ];
+
+    #[test]
+    fn planar_frame_sizes() {
+        for pixfmt in PLANAR.iter() {
+            for (width, height) in [(8, 6), (5, 3), (1, 1)] {
+                let desc = Descriptor {
+                    width,
+                    height,
+                    pixfmt: pixfmt.clone(),
+                    interval: Interval::from_fps(30),
+                };
+                assert!(supported(&desc), "{:?}", pixfmt);
+
+                let mut stream = Handle::new(desc, Pattern::ColorBars, false, false);
+                let format = stream.format();
+                assert_eq!(format.size, format.planes.iter().map(|p| p.size).sum());
+                let frame = stream.next().unwrap().unwrap();
+                assert_eq!(frame.len(), format.size, "{:?}", pixfmt);
+            }
+        }
+    }
+
+    #[test]
+    fn planar_solid_color() {
+        let rgb = [255, 0, 0].repeat(3 * 3);
+        let (y, u, v) = yuv(&rgb[..3]);
+        let mut out = Vec::new();
+
+        // 3x3 luma samples and 2x2 chroma samples per plane
+        convert(&rgb, 3, 3, &PixelFormat::I420(12), &mut out);
+        assert_eq!(out, [vec![y; 9], vec![u; 4], vec![v; 4]].concat());
+        convert(&rgb, 3, 3, &PixelFormat::Yv12(12), &mut out);
+        assert_eq!(out, [vec![y; 9], vec![v; 4], vec![u; 4]].concat());
+        convert(&rgb, 3, 3, &PixelFormat::Nv21(12), &mut out);
+        assert_eq!(out, [vec![y; 9], [v, u].repeat(4)].concat());
+        // 4:2:2 keeps every chroma row
+        convert(&rgb, 3, 3, &PixelFormat::Nv16(16), &mut out);
+        assert_eq!(out, [vec![y; 9], [u, v].repeat(6)].concat());
+        convert(&rgb, 3, 3, &PixelFormat::I444(24), &mut out);
+        assert_eq!(out, [vec![y; 9], vec![u; 9], vec![v; 9]].concat());
+    }
+
+    #[test]
+    fn planar_chroma_average() {
+        // red and blue columns share one chroma sample
+        let rgb = [255, 0, 0, 0, 0, 255].repeat(2);
+        let (_, u0, v0) = yuv(&rgb[..3]);
+        let (_, u1, v1) = yuv(&rgb[3..6]);
+        let mut out = Vec::new();
+
+        convert(&rgb, 2, 2, &PixelFormat::Nv12(12), &mut out);
+        assert_eq!(
+            out[4..],
+            [
+                ((u0 as u16 + u1 as u16) / 2) as u8,
+                ((v0 as u16 + v1 as u16) / 2) as u8
+            ]
+        );
+    }
+
+    #[test]
+    fn bayer_patterns() {
+        let rgb = [10, 20, 30].repeat(4);
+        let mut out = Vec::new();
+
+        let patterns = [
+            (BayerPattern::Bggr, [30, 20, 20, 10]),
+            (BayerPattern::Gbrg, [20, 30, 10, 20]),
+            (BayerPattern::Grbg, [20, 10, 30, 20]),
+            (BayerPattern::Rggb, [10, 20, 20, 30]),
+        ];
+        for (pattern, expected) in patterns {
+            convert(&rgb, 2, 2, &PixelFormat::Bayer(pattern, 8), &mut out);
+            assert_eq!(out, expected);
+        }
+    }
+}
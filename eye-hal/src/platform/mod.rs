@@ -2,12 +2,28 @@
 //!
 //! Multiple backends can be implemented for a given platform.
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::control;
 use crate::device;
 use crate::error::Result;
-use crate::stream::Descriptor as StreamDescriptor;
+use crate::stream::{
+    Descriptor as StreamDescriptor, Format as StreamFormat, Frame, FrameBuf, Pool,
+    Range as StreamRange,
+};
 use crate::traits::{Context as ContextTrait, Device as DeviceTrait, Stream as StreamTrait};
 
+pub mod composite;
+pub mod file;
+pub mod mock;
+pub mod registry;
+
+pub(crate) mod util;
+
+#[cfg(feature = "async")]
+pub(crate) mod channel;
+
 #[cfg(target_os = "linux")]
 pub(crate) mod v4l2;
 
@@ -26,6 +42,14 @@ pub(crate) mod openpnp;
 pub enum Context<'a> {
     /// Can be used to wrap your own struct
     Custom(Box<dyn 'a + ContextTrait<'a, Device = Device<'a>> + Send>),
+    /// Combination of multiple contexts
+    Composite(composite::Context<'a>),
+    /// Context of a backend registered at runtime
+    Registered(registry::Context<'a>),
+    /// Virtual devices for testing
+    Mock(mock::context::Context),
+    /// Playback of recorded files
+    File(file::context::Context),
     #[cfg(target_os = "linux")]
     /// Video4Linux2 context
     V4l2(v4l2::context::Context),
@@ -38,23 +62,63 @@ pub enum Context<'a> {
 }
 
 impl<'a> Context<'a> {
+    /// Returns the contexts of all backends, ordered by preference
+    ///
+    /// This includes the builtin backends compiled in as well as the ones registered at runtime,
+    /// see [`registry`].
     pub fn all() -> impl Iterator<Item = Context<'a>> {
-        std::iter::IntoIterator::into_iter([
+        let builtin = std::iter::IntoIterator::into_iter([
             #[cfg(target_os = "linux")]
             Context::V4l2(v4l2::context::Context {}),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
             Context::Uvc(uvc::context::Context {}),
             #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
             Context::OpenPnP(openpnp::context::Context {}),
-        ])
+            Context::File(file::context::Context {}),
+        ]);
+
+        // The builtin backends have a priority of 0.
+        let (preferred, rest): (Vec<_>, Vec<_>) = registry::backends()
+            .into_iter()
+            .partition(|backend| backend.priority() > 0);
+        let registered = |backend: Arc<dyn registry::Backend>| {
+            Context::Registered(registry::Context::new(&*backend))
+        };
+
+        let contexts: Vec<Context<'a>> = preferred
+            .into_iter()
+            .map(registered)
+            .chain(builtin)
+            .chain(rest.into_iter().map(registered))
+            .collect();
+        contexts.into_iter()
+    }
+
+    /// Returns the URI scheme handled by this context
+    ///
+    /// Contexts which may handle arbitrary schemes, such as custom and composite ones, return
+    /// `None`.
+    pub fn scheme(&self) -> Option<&str> {
+        match self {
+            Self::Custom(_) | Self::Composite(_) => None,
+            Self::Registered(ctx) => Some(ctx.scheme()),
+            Self::Mock(_) => Some("mock"),
+            Self::File(_) => Some("file"),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(_) => Some("v4l"),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(_) => Some("uvc"),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(_) => Some("pnp"),
+        }
     }
 }
 
 impl<'a> Default for Context<'a> {
+    /// Returns a composite context of all backends compiled in, see [`Context::all`]
     fn default() -> Self {
-        Self::all()
-            .next()
-            .expect("no contexts available for this platform")
+        let ctx = Self::all().fold(composite::Context::new(), composite::Context::context);
+        Context::Composite(ctx)
     }
 }
 
@@ -64,6 +128,10 @@ impl<'a> ContextTrait<'a> for Context<'a> {
     fn devices(&self) -> Result<Vec<device::Description>> {
         match self {
             Self::Custom(ctx) => ctx.devices(),
+            Self::Composite(ctx) => ctx.devices(),
+            Self::Registered(ctx) => ctx.devices(),
+            Self::Mock(ctx) => ctx.devices(),
+            Self::File(ctx) => ctx.devices(),
             #[cfg(target_os = "linux")]
             Self::V4l2(ctx) => ctx.devices(),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -76,6 +144,10 @@ impl<'a> ContextTrait<'a> for Context<'a> {
     fn open_device(&self, uri: &str) -> Result<Self::Device> {
         match self {
             Self::Custom(ctx) => ctx.open_device(uri),
+            Self::Composite(ctx) => ctx.open_device(uri),
+            Self::Registered(ctx) => ctx.open_device(uri),
+            Self::Mock(ctx) => Ok(Device::Mock(ctx.open_device(uri)?)),
+            Self::File(ctx) => Ok(Device::File(ctx.open_device(uri)?)),
             #[cfg(target_os = "linux")]
             Self::V4l2(ctx) => Ok(Device::V4l2(ctx.open_device(uri)?)),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -84,6 +156,22 @@ impl<'a> ContextTrait<'a> for Context<'a> {
             Self::OpenPnP(ctx) => Ok(Device::OpenPnP(ctx.open_device(uri)?)),
         }
     }
+
+    fn watch(&self) -> Result<device::Watcher> {
+        match self {
+            Self::Custom(ctx) => ctx.watch(),
+            Self::Composite(ctx) => ctx.watch(),
+            Self::Registered(ctx) => ctx.watch(),
+            Self::Mock(ctx) => ctx.watch(),
+            Self::File(ctx) => ctx.watch(),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(ctx) => ctx.watch(),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(ctx) => ctx.watch(),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(ctx) => ctx.watch(),
+        }
+    }
 }
 
 /// Platform device
@@ -95,6 +183,10 @@ impl<'a> ContextTrait<'a> for Context<'a> {
 pub enum Device<'a> {
     /// Can be used to wrap your own struct
     Custom(Box<dyn 'a + DeviceTrait<'a, Stream = Stream<'a>> + Send>),
+    /// Virtual device handle
+    Mock(mock::device::Handle),
+    /// File playback handle
+    File(file::device::Handle),
     #[cfg(target_os = "linux")]
     /// Video4Linux2 device handle
     V4l2(v4l2::device::Handle),
@@ -112,6 +204,8 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
     fn streams(&self) -> Result<Vec<StreamDescriptor>> {
         match self {
             Self::Custom(dev) => dev.streams(),
+            Self::Mock(dev) => dev.streams(),
+            Self::File(dev) => dev.streams(),
             #[cfg(target_os = "linux")]
             Self::V4l2(dev) => dev.streams(),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -121,9 +215,25 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
         }
     }
 
+    fn stream_ranges(&self) -> Result<Vec<StreamRange>> {
+        match self {
+            Self::Custom(dev) => dev.stream_ranges(),
+            Self::Mock(dev) => dev.stream_ranges(),
+            Self::File(dev) => dev.stream_ranges(),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(dev) => dev.stream_ranges(),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(dev) => dev.stream_ranges(),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(dev) => dev.stream_ranges(),
+        }
+    }
+
     fn controls(&self) -> Result<Vec<control::Descriptor>> {
         match self {
             Self::Custom(dev) => dev.controls(),
+            Self::Mock(dev) => dev.controls(),
+            Self::File(dev) => dev.controls(),
             #[cfg(target_os = "linux")]
             Self::V4l2(dev) => dev.controls(),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -136,6 +246,8 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
     fn control(&self, id: u32) -> Result<control::State> {
         match self {
             Self::Custom(dev) => dev.control(id),
+            Self::Mock(dev) => dev.control(id),
+            Self::File(dev) => dev.control(id),
             #[cfg(target_os = "linux")]
             Self::V4l2(dev) => dev.control(id),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -148,6 +260,8 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
     fn set_control(&mut self, id: u32, val: &control::State) -> Result<()> {
         match self {
             Self::Custom(dev) => dev.set_control(id, val),
+            Self::Mock(dev) => dev.set_control(id, val),
+            Self::File(dev) => dev.set_control(id, val),
             #[cfg(target_os = "linux")]
             Self::V4l2(dev) => dev.set_control(id, val),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -157,9 +271,39 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
         }
     }
 
+    fn control_ids(&self) -> Result<Vec<(u32, Option<control::Kind>)>> {
+        match self {
+            Self::Custom(dev) => dev.control_ids(),
+            Self::Mock(dev) => dev.control_ids(),
+            Self::File(dev) => dev.control_ids(),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(dev) => dev.control_ids(),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(dev) => dev.control_ids(),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(dev) => dev.control_ids(),
+        }
+    }
+
+    fn subscribe(&self) -> Result<control::Subscription<'a>> {
+        match self {
+            Self::Custom(dev) => dev.subscribe(),
+            Self::Mock(dev) => dev.subscribe(),
+            Self::File(dev) => dev.subscribe(),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(dev) => dev.subscribe(),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(dev) => dev.subscribe(),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(dev) => dev.subscribe(),
+        }
+    }
+
     fn start_stream(&self, desc: &StreamDescriptor) -> Result<Self::Stream> {
         match self {
             Self::Custom(dev) => dev.start_stream(desc),
+            Self::Mock(dev) => Ok(Stream::Mock(dev.start_stream(desc)?)),
+            Self::File(dev) => Ok(Stream::File(dev.start_stream(desc)?)),
             #[cfg(target_os = "linux")]
             Self::V4l2(dev) => Ok(Stream::V4l2(dev.start_stream(desc)?)),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -180,10 +324,14 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
 /// the best method available.
 pub enum Stream<'a> {
     /// Can be used to wrap your own struct
-    Custom(Box<dyn 'a + for<'b> StreamTrait<'b, Item = Result<&'b [u8]>> + Send>),
+    Custom(Box<dyn 'a + for<'b> StreamTrait<'b, Item = Result<Frame<'b>>> + Send>),
+    /// Virtual stream handle
+    Mock(mock::stream::Handle),
+    /// File playback stream handle
+    File(file::stream::Handle),
     #[cfg(target_os = "linux")]
     /// Video4Linux2 stream handle
-    V4l2(v4l2::stream::Handle<'a>),
+    V4l2(v4l2::stream::Handle),
     #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
     /// Universal Video Class stream handle
     Uvc(uvc::stream::Handle<'a>),
@@ -193,11 +341,27 @@ pub enum Stream<'a> {
 }
 
 impl<'a, 'b> StreamTrait<'b> for Stream<'a> {
-    type Item = Result<&'b [u8]>;
+    type Item = Result<Frame<'b>>;
+
+    fn format(&self) -> StreamFormat {
+        match self {
+            Self::Custom(stream) => stream.format(),
+            Self::Mock(stream) => stream.format(),
+            Self::File(stream) => stream.format(),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(stream) => stream.format(),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(stream) => stream.format(),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(stream) => stream.format(),
+        }
+    }
 
     fn next(&'b mut self) -> Option<Self::Item> {
         match self {
             Self::Custom(stream) => stream.next(),
+            Self::Mock(stream) => stream.next(),
+            Self::File(stream) => stream.next(),
             #[cfg(target_os = "linux")]
             Self::V4l2(stream) => stream.next(),
             #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
@@ -206,4 +370,211 @@ impl<'a, 'b> StreamTrait<'b> for Stream<'a> {
             Self::OpenPnP(stream) => stream.next(),
         }
     }
+
+    fn next_timeout(&'b mut self, timeout: Duration) -> Option<Self::Item> {
+        match self {
+            Self::Custom(stream) => stream.next_timeout(timeout),
+            Self::Mock(stream) => stream.next_timeout(timeout),
+            Self::File(stream) => stream.next_timeout(timeout),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(stream) => stream.next_timeout(timeout),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(stream) => stream.next_timeout(timeout),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(stream) => stream.next_timeout(timeout),
+        }
+    }
+}
+
+impl<'a> Stream<'a> {
+    /// Captures the next frame as an owned frame buffer
+    ///
+    /// Video4Linux2 streams hand out their memory mapped buffers without copying. Frames of all
+    /// other streams are copied into buffers allocated from the pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - Pool to allocate copies from
+    pub fn next_buf(&mut self, pool: &Pool) -> Option<Result<FrameBuf>> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::V4l2(stream) => Some(stream.next_buf(None)),
+            _ => Some(self.next()?.map(|frame| pool.copy(&frame))),
+        }
+    }
+
+    /// Returns an iterator over owned frames
+    ///
+    /// See [`Stream::next_buf`] for details on how the frames are allocated.
+    pub fn frames(&mut self) -> Frames<'_, 'a> {
+        Frames {
+            stream: self,
+            pool: Pool::default(),
+        }
+    }
+}
+
+/// Iterator over owned frames of a stream
+///
+/// Created by [`Stream::frames`].
+pub struct Frames<'s, 'a> {
+    stream: &'s mut Stream<'a>,
+    pool: Pool,
+}
+
+impl<'s, 'a> Iterator for Frames<'s, 'a> {
+    type Item = Result<FrameBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stream.next_buf(&self.pool)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Stream<'static> {
+    /// Converts the stream into an asynchronous one
+    ///
+    /// On Linux, the device file descriptor is polled for readiness, so no additional threads are
+    /// involved. All other streams are moved to a worker thread which forwards frames through a
+    /// channel. Must be called from within a tokio runtime.
+    ///
+    /// Streams which cannot be moved to another thread are not supported, create them with
+    /// [`AsyncStream::spawn`] instead.
+    pub fn into_async(self) -> Result<AsyncStream> {
+        match self {
+            Self::Custom(stream) => Ok(AsyncStream::spawn(move || Ok(Stream::Custom(stream)))),
+            Self::Mock(stream) => Ok(AsyncStream::spawn(move || Ok(stream))),
+            Self::File(stream) => Ok(AsyncStream::spawn(move || Ok(stream))),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(stream) => Ok(AsyncStream::V4l2(stream.into_async()?)),
+            #[cfg(any(target_os = "windows", feature = "plat-uvc"))]
+            Self::Uvc(_) => Err(crate::Error::new(
+                crate::ErrorKind::NotSupported,
+                "stream cannot be moved to another thread, use AsyncStream::spawn instead",
+            )),
+            #[cfg(any(target_os = "macos", feature = "plat-openpnp"))]
+            Self::OpenPnP(_) => Err(crate::Error::new(
+                crate::ErrorKind::NotSupported,
+                "stream cannot be moved to another thread, use AsyncStream::spawn instead",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+/// Asynchronous platform stream
+///
+/// Leaky abstraction: if you require access to platform specific features, match the enum instance
+/// to get the underlying HAL implementation.
+///
+/// Frames are yielded as [`futures_core::Stream`] items. Since items cannot borrow from the
+/// stream, frames are always copied out of the native buffers.
+pub enum AsyncStream {
+    /// Frames captured by a blocking stream on a worker thread
+    Channel(channel::Handle),
+    #[cfg(target_os = "linux")]
+    /// Video4Linux2 stream handle
+    V4l2(v4l2::stream::AsyncHandle),
+}
+
+#[cfg(feature = "async")]
+impl AsyncStream {
+    /// Creates a blocking stream on a worker thread and forwards its frames
+    ///
+    /// This works for any stream, including those which cannot be moved between threads. If `f`
+    /// fails, the error is yielded as the only item.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Function which creates the blocking stream
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use eye_hal::platform::AsyncStream;
+    /// use eye_hal::traits::{Context, Device};
+    /// use eye_hal::PlatformContext;
+    ///
+    /// let stream = AsyncStream::spawn(|| {
+    ///     let ctx = PlatformContext::default();
+    ///     let devices = ctx.devices()?;
+    ///     let dev = ctx.open_device(&devices[0].uri)?;
+    ///     let desc = dev.streams()?[0].clone();
+    ///     dev.start_stream(&desc)
+    /// });
+    /// ```
+    pub fn spawn<F, S>(f: F) -> Self
+    where
+        F: FnOnce() -> Result<S> + Send + 'static,
+        S: for<'b> StreamTrait<'b, Item = Result<Frame<'b>>>,
+    {
+        AsyncStream::Channel(channel::Handle::spawn(f))
+    }
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for AsyncStream {
+    type Item = Result<Frame<'static>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        match self.get_mut() {
+            Self::Channel(stream) => std::pin::Pin::new(stream).poll_next(cx),
+            #[cfg(target_os = "linux")]
+            Self::V4l2(stream) => std::pin::Pin::new(stream).poll_next(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::ErrorKind;
+
+    /// Captures more frames than any backend has buffers while holding on to all of them
+    ///
+    /// Returns the number of frames captured before the stream ran out of buffers.
+    fn hold_frames(stream: &mut Stream, count: usize) -> usize {
+        let mut held = Vec::new();
+        for item in stream.frames().take(count) {
+            match item {
+                Ok(buf) => held.push(buf),
+                Err(e) => {
+                    assert_eq!(e.kind(), ErrorKind::Busy);
+                    break;
+                }
+            }
+        }
+        held.len()
+    }
+
+    #[test]
+    fn hold_all_frames_mock() {
+        let ctx = Context::Mock(mock::Context::default());
+        let dev = ctx.open_device("mock://0").unwrap();
+        let desc = dev.streams().unwrap()[0].clone();
+        let mut stream = dev.start_stream(&desc).unwrap();
+
+        // frames are copied, so the stream never runs out of buffers
+        assert_eq!(hold_frames(&mut stream, 8), 8);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "requires a capture device"]
+    fn hold_all_frames_v4l2() {
+        let ctx = Context::V4l2(v4l2::context::Context {});
+        let devices = ctx.devices().unwrap();
+        let desc = devices.first().expect("no capture device attached");
+        let dev = ctx.open_device(&desc.uri).unwrap();
+        let desc = dev.streams().unwrap()[0].clone();
+        let mut stream = dev.start_stream(&desc).unwrap();
+
+        // the driver buffers run out eventually, which must not block forever
+        let count = hold_frames(&mut stream, 64);
+        assert!(count > 0 && count < 64);
+    }
 }
@@ -17,6 +17,8 @@ impl<'a> ContextTrait<'a> for Context {
             .map(|i| device::Description {
                 uri: format!("pnp://{}", i),
                 product: "Unknown OpenPnP device".to_string(),
+                backend: "openpnp".to_string(),
+                ..Default::default()
             })
             .collect();
 
@@ -31,7 +33,7 @@ impl<'a> ContextTrait<'a> for Context {
             };
             Ok(handle)
         } else {
-            Err(Error::new(ErrorKind::Other, "invalid URI"))
+            Err(Error::new(ErrorKind::InvalidArgument, "invalid URI"))
         }
     }
 }
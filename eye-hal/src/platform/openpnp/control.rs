@@ -8,31 +8,103 @@ pub(crate) enum Typ {
     Auto,
 }
 
-const ALL: [(u32, &str, Typ); 17] = [
-    (sys::CAPPROPID_EXPOSURE, "Exposure", Typ::Limited),
-    (sys::CAPPROPID_EXPOSURE, "Auto Exposure", Typ::Auto),
-    (sys::CAPPROPID_FOCUS, "Focus", Typ::Limited),
-    (sys::CAPPROPID_FOCUS, "Auto Focus", Typ::Auto),
-    (sys::CAPPROPID_ZOOM, "Zoom", Typ::Limited),
-    (sys::CAPPROPID_WHITEBALANCE, "White Balance", Typ::Limited),
-    (sys::CAPPROPID_WHITEBALANCE, "Auto White Balance", Typ::Auto),
-    (sys::CAPPROPID_GAIN, "Gain", Typ::Limited),
-    (sys::CAPPROPID_GAIN, "Auto Gain", Typ::Auto),
-    (sys::CAPPROPID_BRIGHTNESS, "Brightness", Typ::Limited),
-    (sys::CAPPROPID_CONTRAST, "Contrast", Typ::Limited),
-    (sys::CAPPROPID_SATURATION, "Saturation", Typ::Limited),
-    (sys::CAPPROPID_GAMMA, "Gamma", Typ::Limited),
-    (sys::CAPPROPID_HUE, "Hue", Typ::Limited),
-    (sys::CAPPROPID_SHARPNESS, "Sharpness", Typ::Limited),
+const ALL: [(u32, &str, Typ, control::Kind); 17] = [
+    (
+        sys::CAPPROPID_EXPOSURE,
+        "Exposure",
+        Typ::Limited,
+        control::Kind::Exposure,
+    ),
+    (
+        sys::CAPPROPID_EXPOSURE,
+        "Auto Exposure",
+        Typ::Auto,
+        control::Kind::AutoExposure,
+    ),
+    (
+        sys::CAPPROPID_FOCUS,
+        "Focus",
+        Typ::Limited,
+        control::Kind::Focus,
+    ),
+    (
+        sys::CAPPROPID_FOCUS,
+        "Auto Focus",
+        Typ::Auto,
+        control::Kind::AutoFocus,
+    ),
+    (
+        sys::CAPPROPID_ZOOM,
+        "Zoom",
+        Typ::Limited,
+        control::Kind::Zoom,
+    ),
+    (
+        sys::CAPPROPID_WHITEBALANCE,
+        "White Balance",
+        Typ::Limited,
+        control::Kind::WhiteBalance,
+    ),
+    (
+        sys::CAPPROPID_WHITEBALANCE,
+        "Auto White Balance",
+        Typ::Auto,
+        control::Kind::AutoWhiteBalance,
+    ),
+    (
+        sys::CAPPROPID_GAIN,
+        "Gain",
+        Typ::Limited,
+        control::Kind::Gain,
+    ),
+    (
+        sys::CAPPROPID_GAIN,
+        "Auto Gain",
+        Typ::Auto,
+        control::Kind::AutoGain,
+    ),
+    (
+        sys::CAPPROPID_BRIGHTNESS,
+        "Brightness",
+        Typ::Limited,
+        control::Kind::Brightness,
+    ),
+    (
+        sys::CAPPROPID_CONTRAST,
+        "Contrast",
+        Typ::Limited,
+        control::Kind::Contrast,
+    ),
+    (
+        sys::CAPPROPID_SATURATION,
+        "Saturation",
+        Typ::Limited,
+        control::Kind::Saturation,
+    ),
+    (
+        sys::CAPPROPID_GAMMA,
+        "Gamma",
+        Typ::Limited,
+        control::Kind::Gamma,
+    ),
+    (sys::CAPPROPID_HUE, "Hue", Typ::Limited, control::Kind::Hue),
+    (
+        sys::CAPPROPID_SHARPNESS,
+        "Sharpness",
+        Typ::Limited,
+        control::Kind::Sharpness,
+    ),
     (
         sys::CAPPROPID_BACKLIGHTCOMP,
         "Backlight Compensation",
         Typ::Limited,
+        control::Kind::BacklightCompensation,
     ),
     (
         sys::CAPPROPID_POWERLINEFREQ,
         "Powerline Frequency",
         Typ::Limited,
+        control::Kind::PowerLineFrequency,
     ),
 ];
 
@@ -42,7 +114,7 @@ pub fn all(
 ) -> impl IntoIterator<Item = control::Descriptor> {
     ALL.iter()
         .enumerate()
-        .filter_map(move |(i, (id, name, typ))| {
+        .filter_map(move |(i, (id, name, typ, kind))| {
             // check whether the control is available and parse its properties
             match typ {
                 Typ::Limited => unsafe {
@@ -58,11 +130,14 @@ pub fn all(
                         sys::CAPRESULT_OK => Some(control::Descriptor {
                             id: i as u32,
                             name: name.to_string(),
+                            kind: Some(*kind),
                             typ: control::Type::Number {
                                 range: (min as f64, max as f64),
                                 step: 1.0,
                             },
                             flags: control::Flags::READ | control::Flags::WRITE,
+                            default: Some(control::State::Number(default as f64)),
+                            value: read(ctx, stream, i as u32).ok(),
                         }),
                         _ => {
                             // either the property is not available or there was an error
@@ -77,8 +152,11 @@ pub fn all(
                         sys::CAPRESULT_OK => Some(control::Descriptor {
                             id: i as u32,
                             name: name.to_string(),
+                            kind: Some(*kind),
                             typ: control::Type::Boolean,
                             flags: control::Flags::READ | control::Flags::WRITE,
+                            default: None,
+                            value: Some(control::State::Boolean(on_off != 0)),
                         }),
                         _ => {
                             // either the property is not available or there was an error
@@ -91,14 +169,14 @@ pub fn all(
 }
 
 pub fn read(ctx: sys::CapContext, stream: sys::CapStream, id: u32) -> Result<control::State> {
-    let (id, _name, typ) = &ALL[id as usize];
+    let (id, _name, typ, _kind) = &ALL[id as usize];
     match typ {
         Typ::Limited => unsafe {
             let mut value = 0;
             match sys::Cap_getProperty(ctx, stream, *id, &mut value) {
                 sys::CAPRESULT_OK => Ok(control::State::Number(value as f64)),
                 sys::CAPRESULT_PROPERTYNOTSUPPORTED => {
-                    Err(Error::new(ErrorKind::Other, "property not available"))
+                    Err(Error::new(ErrorKind::NotFound, "property not available"))
                 }
                 _ => Err(Error::new(ErrorKind::Other, "unknown error")),
             }
@@ -108,7 +186,7 @@ pub fn read(ctx: sys::CapContext, stream: sys::CapStream, id: u32) -> Result<con
             match sys::Cap_getAutoProperty(ctx, stream, *id, &mut on_off) {
                 sys::CAPRESULT_OK => Ok(control::State::Boolean(on_off != 0)),
                 sys::CAPRESULT_PROPERTYNOTSUPPORTED => {
-                    Err(Error::new(ErrorKind::Other, "property not available"))
+                    Err(Error::new(ErrorKind::NotFound, "property not available"))
                 }
                 _ => Err(Error::new(ErrorKind::Other, "unknown error")),
             }
@@ -122,18 +200,21 @@ pub fn write(
     id: u32,
     value: &control::State,
 ) -> Result<()> {
-    let (id, _name, typ) = &ALL[id as usize];
+    let (id, _name, typ, _kind) = &ALL[id as usize];
     match typ {
         Typ::Limited => unsafe {
             let value = if let control::State::Number(value) = value {
                 value
             } else {
-                return Err(Error::new(ErrorKind::Other, "invalid control state"));
+                return Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    "invalid control state",
+                ));
             };
             match sys::Cap_setProperty(ctx, stream, *id, *value as i32) {
                 sys::CAPRESULT_OK => Ok(()),
                 sys::CAPRESULT_PROPERTYNOTSUPPORTED => {
-                    Err(Error::new(ErrorKind::Other, "property not available"))
+                    Err(Error::new(ErrorKind::NotFound, "property not available"))
                 }
                 _ => Err(Error::new(ErrorKind::Other, "unknown error")),
             }
@@ -146,12 +227,15 @@ pub fn write(
                     0
                 }
             } else {
-                return Err(Error::new(ErrorKind::Other, "invalid control state"));
+                return Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    "invalid control state",
+                ));
             };
             match sys::Cap_getAutoProperty(ctx, stream, *id, &mut on_off) {
                 sys::CAPRESULT_OK => Ok(()),
                 sys::CAPRESULT_PROPERTYNOTSUPPORTED => {
-                    Err(Error::new(ErrorKind::Other, "property not available"))
+                    Err(Error::new(ErrorKind::NotFound, "property not available"))
                 }
                 _ => Err(Error::new(ErrorKind::Other, "unknown error")),
             }
@@ -1,13 +1,13 @@
 use std::cell::Cell;
-use std::convert::TryInto;
-use std::{io, time};
+use std::convert::TryFrom;
+use std::io;
 
 use openpnp_capture as pnp;
 use openpnp_capture_sys as sys;
 
 use crate::control;
 use crate::error::{Error, ErrorKind, Result};
-use crate::format::PixelFormat;
+use crate::format::{FourCC, PixelFormat};
 use crate::platform::openpnp::control as pnp_ctrl;
 use crate::platform::openpnp::stream::Handle as StreamHandle;
 use crate::stream;
@@ -32,14 +32,17 @@ impl Handle {
         if uri.starts_with("pnp://") {
             let elems: Vec<&str> = uri[6..].split(':').collect();
             if elems.is_empty() {
-                return Err(io::Error::new(io::ErrorKind::Other, "missing index in URI"));
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "missing index in URI",
+                ));
             }
 
             let index = if let Ok(index) = elems[0].parse::<u32>() {
                 index
             } else {
                 return Err(io::Error::new(
-                    io::ErrorKind::Other,
+                    io::ErrorKind::InvalidInput,
                     "malformed index in URI",
                 ));
             };
@@ -49,7 +52,10 @@ impl Handle {
                 "failed to create OpenPnP capture instance",
             ))
         } else {
-            Err(io::Error::new(io::ErrorKind::Other, "not a pnp:// URI!"))
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a pnp:// URI!",
+            ))
         }
     }
 }
@@ -64,8 +70,8 @@ impl<'a> Device<'a> for Handle {
             .map(|fmt| stream::Descriptor {
                 width: fmt.width,
                 height: fmt.height,
-                pixfmt: PixelFormat::Custom(fmt.fourcc.to_string()),
-                interval: time::Duration::from_secs_f32(1.0 / fmt.fps as f32),
+                pixfmt: PixelFormat::from(FourCC::new(&fmt.fourcc.repr)),
+                interval: stream::Interval::from_fps(fmt.fps),
             })
             .collect();
 
@@ -108,21 +114,15 @@ impl<'a> Device<'a> for Handle {
     }
 
     fn start_stream(&self, desc: &stream::Descriptor) -> Result<Self::Stream> {
-        let fourcc = match desc.pixfmt.clone() {
-            PixelFormat::Rgb(24) => *b"RGB3",
-            PixelFormat::Custom(repr) => {
-                if repr.len() == 4 {
-                    repr.as_bytes().try_into().unwrap()
-                } else {
-                    return Err(Error::new(ErrorKind::NotSupported, "invalid fourcc"));
-                }
-            }
-            _ => {
-                return Err(Error::new(
-                    ErrorKind::NotSupported,
-                    "cannot map pixfmt to fourcc",
-                ))
-            }
+        // use the fourcc reported by the device, it may differ from the V4L2 one
+        let native = self.inner.formats().into_iter().find(|fmt| {
+            fmt.width == desc.width
+                && fmt.height == desc.height
+                && PixelFormat::from(FourCC::new(&fmt.fourcc.repr)) == desc.pixfmt
+        });
+        let fourcc = match native {
+            Some(fmt) => fmt.fourcc.repr,
+            None => FourCC::try_from(&desc.pixfmt)?.repr,
         };
 
         let fmt = pnp::Format {
@@ -130,10 +130,17 @@ impl<'a> Device<'a> for Handle {
             height: desc.height,
             fourcc: pnp::format::FourCC::new(&fourcc),
             bpp: 0,
-            fps: 0,
+            // OpenPnP only knows about integer frame rates
+            fps: desc.interval.fps().round() as u32,
         };
 
-        let handle = StreamHandle::new(&self.inner, &fmt)?;
+        // openpnp-capture hands out RGB frames, no matter which format the camera delivers
+        let format = stream::Format::packed(&stream::Descriptor {
+            pixfmt: PixelFormat::Rgb(24),
+            ..desc.clone()
+        });
+
+        let handle = StreamHandle::new(&self.inner, &fmt, format)?;
         self.stream_id.set(Some(handle.inner.id()));
         Ok(handle)
     }
@@ -177,4 +184,20 @@ impl<'a> Device<'a> for Handle {
         let pnp_ctx = pnp::context::CONTEXT.lock().unwrap().inner;
         pnp_ctrl::write(pnp_ctx, stream_id, id, val)
     }
+
+    fn subscribe(&self) -> Result<control::Subscription<'a>> {
+        // Ensure a stream is currently open.
+        // This is required because openpnp is weird in that it insists to perform control
+        // operations on stream object instead of device ones.
+        let stream_id = self
+            .stream_id
+            .get()
+            .ok_or(Error::new(ErrorKind::Other, "stream not running"))?;
+
+        // openpnp does not report control changes, so they are polled
+        control::Subscription::poll(move || {
+            let pnp_ctx = pnp::context::CONTEXT.lock().unwrap().inner;
+            Ok(pnp_ctrl::all(pnp_ctx, stream_id).into_iter().collect())
+        })
+    }
 }
@@ -1,18 +1,27 @@
 use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use openpnp_capture as pnp;
 
 use crate::error::Result;
+use crate::stream::{Format, Frame};
 use crate::traits::Stream;
 use crate::{Error, ErrorKind};
 
+/// Time to wait between two attempts to fetch a frame
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
 pub struct Handle {
     pub(crate) inner: pnp::Stream,
+    format: Format,
     buffer: Vec<u8>,
+    epoch: Instant,
+    sequence: u64,
 }
 
 impl Handle {
-    pub fn new(dev: &pnp::Device, fmt: &pnp::Format) -> io::Result<Self> {
+    pub fn new(dev: &pnp::Device, fmt: &pnp::Format, format: Format) -> io::Result<Self> {
         let pnp_stream = match pnp::Stream::new(dev, fmt) {
             Some(stream) => stream,
             None => {
@@ -25,21 +34,64 @@ impl Handle {
 
         Ok(Handle {
             inner: pnp_stream,
+            format,
             buffer: Vec::new(),
+            epoch: Instant::now(),
+            sequence: 0,
         })
     }
 }
 
+impl Handle {
+    /// Waits for a new frame and reads it into the buffer
+    fn read(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        while !self.inner.poll() {
+            if let Some(deadline) = deadline {
+                if Instant::now() >= deadline {
+                    return Err(Error::new(ErrorKind::Timeout, "no frame available"));
+                }
+            }
+
+            // openpnp-capture offers no way to wait for a frame, so check back shortly
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        self.inner
+            .read(&mut self.buffer)
+            .map_err(|e| Error::new(ErrorKind::Other, e))
+    }
+
+    fn frame(&mut self) -> Frame<'_> {
+        // openpnp-capture does not provide any frame metadata, so we use the time of arrival
+        // and count the frames ourselves.
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        Frame::new(&self.buffer[..])
+            .timestamp(self.epoch.elapsed())
+            .sequence(sequence)
+    }
+}
+
 impl<'a> Stream<'a> for Handle {
-    type Item = Result<&'a [u8]>;
+    type Item = Result<Frame<'a>>;
+
+    fn format(&self) -> Format {
+        self.format.clone()
+    }
 
     fn next(&'a mut self) -> Option<Self::Item> {
-        while !self.inner.poll() { /* busy loop */ }
-        match self.inner.read(&mut self.buffer) {
-            Ok(()) => {}
-            Err(e) => return Some(Err(Error::new(ErrorKind::Other, e))),
+        match self.read(None) {
+            Ok(()) => Some(Ok(self.frame())),
+            Err(e) => Some(Err(e)),
         }
+    }
 
-        Some(Ok(&self.buffer))
+    fn next_timeout(&'a mut self, timeout: Duration) -> Option<Self::Item> {
+        match self.read(Some(timeout)) {
+            Ok(()) => Some(Ok(self.frame())),
+            Err(e) => Some(Err(e)),
+        }
     }
 }
@@ -0,0 +1,199 @@
+//! Runtime registry for third-party backends
+//!
+//! Backends which are not part of this crate can be registered under a URI scheme. Registered
+//! backends are included in [`Context::all`](crate::platform::Context::all) and thus in the
+//! default context, so devices are enumerated and opened just like the ones of the builtin
+//! backends.
+//!
+//! # Example
+//!
+//! ```
+//! use eye_hal::platform::{mock, registry, Context};
+//! use eye_hal::traits::Context as _;
+//!
+//! struct Virtual;
+//!
+//! impl registry::Backend for Virtual {
+//!     fn scheme(&self) -> &str {
+//!         "mock"
+//!     }
+//!
+//!     fn context<'a>(&self) -> Context<'a> {
+//!         Context::Mock(mock::Context::default())
+//!     }
+//! }
+//!
+//! registry::register(Virtual);
+//!
+//! let ctx = Context::default();
+//! let devices = ctx.devices().unwrap();
+//! assert!(devices.iter().any(|desc| desc.uri == "mock://0"));
+//! assert!(ctx.open_device("mock://0").is_ok());
+//! ```
+
+use std::cmp::Reverse;
+use std::sync::{Arc, Mutex};
+
+use crate::device;
+use crate::error::Result;
+use crate::platform::{Context as PlatformContext, Device as PlatformDevice};
+use crate::traits::Context as ContextTrait;
+
+/// Registered backends, in registration order
+static BACKENDS: Mutex<Vec<Arc<dyn Backend>>> = Mutex::new(Vec::new());
+
+/// Backend factory
+pub trait Backend: Send + Sync {
+    /// URI scheme of the devices provided by this backend, e.g. "gige" for "gige://..."
+    fn scheme(&self) -> &str;
+
+    /// Preference of this backend compared to the others
+    ///
+    /// Backends are enumerated in order of descending priority. The builtin backends have a
+    /// priority of 0 and precede registered backends of the same priority.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Returns a new context of this backend
+    fn context<'a>(&self) -> PlatformContext<'a>;
+}
+
+/// Registers a backend
+///
+/// Backends registered under a scheme which is taken already are enumerated after the existing
+/// ones of the same priority.
+///
+/// # Arguments
+///
+/// * `backend` - Backend to register
+pub fn register<B: Backend + 'static>(backend: B) {
+    let mut backends = BACKENDS.lock().unwrap_or_else(|e| e.into_inner());
+    backends.push(Arc::new(backend));
+}
+
+/// Removes all backends registered under a scheme, returns false if there were none
+///
+/// Contexts created before are not affected.
+///
+/// # Arguments
+///
+/// * `scheme` - URI scheme
+pub fn unregister(scheme: &str) -> bool {
+    let mut backends = BACKENDS.lock().unwrap_or_else(|e| e.into_inner());
+    let len = backends.len();
+    backends.retain(|backend| backend.scheme() != scheme);
+    backends.len() != len
+}
+
+/// Returns all registered backends in order of descending priority
+pub fn backends() -> Vec<Arc<dyn Backend>> {
+    let mut backends = BACKENDS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    // stable sort, so backends of the same priority stay in registration order
+    backends.sort_by_key(|backend| Reverse(backend.priority()));
+    backends
+}
+
+/// Runtime context
+///
+/// Context of a registered backend, tagged with the URI scheme it was registered under.
+pub struct Context<'a> {
+    scheme: String,
+    inner: Box<PlatformContext<'a>>,
+}
+
+impl<'a> Context<'a> {
+    /// Creates a new context of a backend
+    pub fn new(backend: &dyn Backend) -> Self {
+        Context {
+            scheme: backend.scheme().to_string(),
+            inner: Box::new(backend.context()),
+        }
+    }
+
+    /// Returns the URI scheme handled by this context
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Returns the context created by the backend
+    pub fn inner(&self) -> &PlatformContext<'a> {
+        &self.inner
+    }
+}
+
+impl<'a> ContextTrait<'a> for Context<'a> {
+    type Device = PlatformDevice<'a>;
+
+    fn devices(&self) -> Result<Vec<device::Description>> {
+        self.inner.devices()
+    }
+
+    fn open_device(&self, uri: &str) -> Result<Self::Device> {
+        self.inner.open_device(uri)
+    }
+
+    fn watch(&self) -> Result<device::Watcher> {
+        self.inner.watch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::platform::mock;
+
+    struct Prioritized(&'static str, i32);
+
+    impl Backend for Prioritized {
+        fn scheme(&self) -> &str {
+            self.0
+        }
+
+        fn priority(&self) -> i32 {
+            self.1
+        }
+
+        fn context<'a>(&self) -> PlatformContext<'a> {
+            PlatformContext::Mock(mock::Context::default())
+        }
+    }
+
+    #[test]
+    fn priority_order() {
+        let schemes = [
+            "prio-low",
+            "prio-min",
+            "prio-tie-1",
+            "prio-high",
+            "prio-tie-2",
+        ];
+        register(Prioritized(schemes[0], -1));
+        register(Prioritized(schemes[1], i32::MIN));
+        register(Prioritized(schemes[2], 0));
+        register(Prioritized(schemes[3], i32::MAX));
+        register(Prioritized(schemes[4], 0));
+
+        let order: Vec<String> = backends()
+            .iter()
+            .map(|backend| backend.scheme().to_string())
+            .filter(|scheme| scheme.starts_with("prio-"))
+            .collect();
+        for scheme in &schemes {
+            assert!(unregister(scheme));
+        }
+
+        // backends of the same priority stay in registration order
+        assert_eq!(
+            order,
+            [
+                "prio-high",
+                "prio-tie-1",
+                "prio-tie-2",
+                "prio-low",
+                "prio-min"
+            ]
+        );
+    }
+}
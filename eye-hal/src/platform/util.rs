@@ -0,0 +1,33 @@
+//! Helpers shared by the software backends
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, ErrorKind, Result};
+
+/// Sleeps until the deadline, unless it lies beyond the timeout
+///
+/// Used to pace frames in realtime. If the deadline cannot be met within the timeout, the whole
+/// timeout is slept and an error of kind [`ErrorKind::Timeout`] is returned.
+///
+/// # Arguments
+///
+/// * `deadline` - Point in time at which the next frame is due
+/// * `timeout` - Maximum time to wait, if any
+pub(crate) fn wait(deadline: Instant, timeout: Option<Duration>) -> Result<()> {
+    let now = Instant::now();
+    if deadline <= now {
+        return Ok(());
+    }
+
+    match timeout {
+        Some(timeout) if deadline - now > timeout => {
+            thread::sleep(timeout);
+            Err(Error::new(ErrorKind::Timeout, "no frame available"))
+        }
+        _ => {
+            thread::sleep(deadline - now);
+            Ok(())
+        }
+    }
+}
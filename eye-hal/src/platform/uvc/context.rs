@@ -12,7 +12,7 @@ impl<'a> ContextTrait<'a> for Context {
     fn devices(&self) -> Result<Vec<device::Description>> {
         let ctx = match uvc::Context::new() {
             Ok(ctx) => ctx,
-            Err(e) => return Err(Error::new(ErrorKind::Other, e)),
+            Err(e) => return Err(Error::from(e)),
         };
 
         let devices = match ctx.devices() {
@@ -22,12 +22,21 @@ impl<'a> ContextTrait<'a> for Context {
                     let mut description = device::Description {
                         uri: format!("uvc://{}:{}", dev.bus_number(), dev.device_address()),
                         product: "Unknown UVC device".to_string(),
+                        backend: "uvc".to_string(),
+                        ..Default::default()
                     };
 
                     if let Ok(desc) = dev.description() {
                         if let Some(product) = desc.product {
                             description.product = product;
                         }
+                        description.usb = Some(device::Usb {
+                            vendor_id: desc.vendor_id,
+                            product_id: desc.product_id,
+                            serial: desc.serial_number,
+                            bus_number: dev.bus_number(),
+                            address: dev.device_address(),
+                        });
                     }
 
                     // If the parsing of hardware information (e.g. product name) failed, there's
@@ -39,7 +48,7 @@ impl<'a> ContextTrait<'a> for Context {
                     description
                 })
                 .collect(),
-            Err(e) => return Err(Error::new(ErrorKind::Other, e)),
+            Err(e) => return Err(Error::from(e)),
         };
 
         Ok(devices)
@@ -49,11 +58,11 @@ impl<'a> ContextTrait<'a> for Context {
         if uri.starts_with("uvc://") {
             let handle = match DeviceHandle::with_uri(uri) {
                 Ok(handle) => handle,
-                Err(e) => return Err(Error::new(ErrorKind::Other, format!("UVC: {}", e))),
+                Err(e) => return Err(Error::from(e)),
             };
             Ok(handle)
         } else {
-            Err(Error::new(ErrorKind::Other, "invalid URI"))
+            Err(Error::new(ErrorKind::InvalidArgument, "invalid URI"))
         }
     }
 }
@@ -1,5 +1,5 @@
 use crate::control;
-use crate::error::{Error, ErrorKind, Result};
+use crate::error::{Error, Result};
 
 pub(crate) enum Control {
     ScanningMode,
@@ -58,35 +58,50 @@ impl Control {
         }
     }
 
+    pub fn kind(&self) -> Option<control::Kind> {
+        match self {
+            Control::AutoExposureMode => Some(control::Kind::AutoExposure),
+            Control::ExposureAbsolute => Some(control::Kind::Exposure),
+            Control::FocusAbsolute => Some(control::Kind::Focus),
+            _ => None,
+        }
+    }
+
     pub fn get(&self, handle: &uvc::DeviceHandle) -> Result<control::State> {
         match self {
+            // menu items are indexed by their UVC values
             Control::ScanningMode => match handle.scanning_mode() {
-                Ok(mode) => Ok(control::State::String(format!("{:?}", mode))),
-                Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                Ok(uvc::ScanningMode::Interlaced) => Ok(control::State::MenuIndex(0)),
+                Ok(uvc::ScanningMode::Progressive) => Ok(control::State::MenuIndex(1)),
+                Err(e) => Err(Error::from(e)),
             },
             Control::AutoExposureMode => match handle.ae_mode() {
-                Ok(mode) => Ok(control::State::String(format!("{:?}", mode))),
-                Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                Ok(uvc::AutoExposureMode::Manual) => Ok(control::State::MenuIndex(1)),
+                Ok(uvc::AutoExposureMode::Auto) => Ok(control::State::MenuIndex(2)),
+                Ok(uvc::AutoExposureMode::ShutterPriority) => Ok(control::State::MenuIndex(4)),
+                Ok(uvc::AutoExposureMode::AperturePriority) => Ok(control::State::MenuIndex(8)),
+                Err(e) => Err(Error::from(e)),
             },
             Control::AutoExposurePriority => match handle.ae_priority() {
-                Ok(mode) => Ok(control::State::String(format!("{:?}", mode))),
-                Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                Ok(uvc::AutoExposurePriority::Constant) => Ok(control::State::MenuIndex(0)),
+                Ok(uvc::AutoExposurePriority::Variable) => Ok(control::State::MenuIndex(1)),
+                Err(e) => Err(Error::from(e)),
             },
             Control::ExposureAbsolute => match handle.exposure_abs() {
                 Ok(val) => Ok(control::State::Number(val as f64)),
-                Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                Err(e) => Err(Error::from(e)),
             },
             Control::ExposureRelative => match handle.exposure_rel() {
                 Ok(val) => Ok(control::State::Number(val as f64)),
-                Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                Err(e) => Err(Error::from(e)),
             },
             Control::FocusAbsolute => match handle.focus_abs() {
                 Ok(val) => Ok(control::State::Number(val as f64)),
-                Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                Err(e) => Err(Error::from(e)),
             },
             Control::FocusRelative => match handle.focus_rel() {
                 Ok((val, _speed)) => Ok(control::State::Number(val as f64)),
-                Err(e) => Err(Error::new(ErrorKind::Other, e)),
+                Err(e) => Err(Error::from(e)),
             },
         }
     }
@@ -98,36 +113,54 @@ impl From<&Control> for control::Descriptor {
             Control::ScanningMode => control::Descriptor {
                 id: ctrl.id(),
                 name: String::from(ctrl.name()),
+                kind: ctrl.kind(),
                 flags: control::Flags::READ,
+                default: None,
+                value: None,
                 typ: control::Type::Menu(vec![
-                    control::MenuItem::String(String::from("Interlaced")),
-                    control::MenuItem::String(String::from("Progressive")),
+                    (0, control::MenuItem::String(String::from("Interlaced"))),
+                    (1, control::MenuItem::String(String::from("Progressive"))),
                 ]),
             },
             Control::AutoExposureMode => control::Descriptor {
                 id: ctrl.id(),
                 name: String::from(ctrl.name()),
+                kind: ctrl.kind(),
                 flags: control::Flags::READ,
+                default: None,
+                value: None,
                 typ: control::Type::Menu(vec![
-                    control::MenuItem::String(String::from("Manual")),
-                    control::MenuItem::String(String::from("Auto")),
-                    control::MenuItem::String(String::from("ShutterPriority")),
-                    control::MenuItem::String(String::from("AperturePriority")),
+                    (1, control::MenuItem::String(String::from("Manual"))),
+                    (2, control::MenuItem::String(String::from("Auto"))),
+                    (
+                        4,
+                        control::MenuItem::String(String::from("ShutterPriority")),
+                    ),
+                    (
+                        8,
+                        control::MenuItem::String(String::from("AperturePriority")),
+                    ),
                 ]),
             },
             Control::AutoExposurePriority => control::Descriptor {
                 id: ctrl.id(),
                 name: String::from(ctrl.name()),
+                kind: ctrl.kind(),
                 flags: control::Flags::READ,
+                default: None,
+                value: None,
                 typ: control::Type::Menu(vec![
-                    control::MenuItem::String(String::from("Constant")),
-                    control::MenuItem::String(String::from("Variable")),
+                    (0, control::MenuItem::String(String::from("Constant"))),
+                    (1, control::MenuItem::String(String::from("Variable"))),
                 ]),
             },
             Control::ExposureAbsolute => control::Descriptor {
                 id: ctrl.id(),
                 name: String::from(ctrl.name()),
+                kind: ctrl.kind(),
                 flags: control::Flags::READ,
+                default: None,
+                value: None,
                 typ: control::Type::Number {
                     range: (u32::MIN as f64, u32::MAX as f64),
                     step: 1.0,
@@ -136,7 +169,10 @@ impl From<&Control> for control::Descriptor {
             Control::ExposureRelative => control::Descriptor {
                 id: ctrl.id(),
                 name: String::from(ctrl.name()),
+                kind: ctrl.kind(),
                 flags: control::Flags::READ,
+                default: None,
+                value: None,
                 typ: control::Type::Number {
                     range: (i8::MIN as f64, i8::MAX as f64),
                     step: 1.0,
@@ -145,7 +181,10 @@ impl From<&Control> for control::Descriptor {
             Control::FocusAbsolute => control::Descriptor {
                 id: ctrl.id(),
                 name: String::from(ctrl.name()),
+                kind: ctrl.kind(),
                 flags: control::Flags::READ,
+                default: None,
+                value: None,
                 typ: control::Type::Number {
                     range: (u16::MIN as f64, u16::MAX as f64),
                     step: 1.0,
@@ -154,7 +193,10 @@ impl From<&Control> for control::Descriptor {
             Control::FocusRelative => control::Descriptor {
                 id: ctrl.id(),
                 name: String::from(ctrl.name()),
+                kind: ctrl.kind(),
                 flags: control::Flags::READ,
+                default: None,
+                value: None,
                 typ: control::Type::Number {
                     range: (i8::MIN as f64, i8::MAX as f64),
                     step: 1.0,
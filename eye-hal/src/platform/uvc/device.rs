@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 use crate::control;
@@ -26,23 +27,23 @@ impl<'a> Handle<'a> {
         if uri.starts_with("uvc://") {
             let elems: Vec<&str> = uri[6..].split(':').collect();
             if elems.len() < 2 {
-                return Err(uvc::Error::Other);
+                return Err(uvc::Error::InvalidParam);
             }
 
             let bus_number = if let Ok(index) = elems[0].parse::<u8>() {
                 index
             } else {
-                return Err(uvc::Error::Other);
+                return Err(uvc::Error::InvalidParam);
             };
             let device_address = if let Ok(addr) = elems[1].parse::<u8>() {
                 addr
             } else {
-                return Err(uvc::Error::Other);
+                return Err(uvc::Error::InvalidParam);
             };
 
             Self::new(bus_number, device_address)
         } else {
-            Err(uvc::Error::Other)
+            Err(uvc::Error::InvalidParam)
         }
     }
 }
@@ -62,18 +63,22 @@ impl<'a> Device<'a> for Handle<'a> {
                     .supported_formats()
                     .into_iter()
                     .for_each(|frame_desc| {
+                        // The uvc crate only tells MJPEG and uncompressed descriptors apart, it
+                        // does not expose the GUID of the latter. Uncompressed frames are
+                        // converted to RGB by the stream handle anyway.
                         let pixfmt = match frame_desc.subtype() {
                             uvc::DescriptionSubtype::FormatMJPEG
                             | uvc::DescriptionSubtype::FrameMJPEG => PixelFormat::Jpeg,
                             _ => PixelFormat::Rgb(24),
                         };
 
-                        for interval in frame_desc.intervals_duration() {
+                        // UVC intervals are specified in units of 100 ns
+                        for interval in frame_desc.intervals() {
                             streams.push(stream::Descriptor {
                                 width: frame_desc.width() as u32,
                                 height: frame_desc.height() as u32,
                                 pixfmt: pixfmt.clone(),
-                                interval,
+                                interval: stream::Interval::new(*interval, 10_000_000),
                             });
                         }
                     });
@@ -85,7 +90,10 @@ impl<'a> Device<'a> for Handle<'a> {
     fn controls(&self) -> Result<Vec<control::Descriptor>> {
         let controls = Control::all()
             .into_iter()
-            .map(|ctrl| <control::Descriptor>::from(&ctrl))
+            .map(|ctrl| control::Descriptor {
+                value: ctrl.get(&self.inner.handle).ok(),
+                ..<control::Descriptor>::from(&ctrl)
+            })
             .collect();
         Ok(controls)
     }
@@ -93,7 +101,7 @@ impl<'a> Device<'a> for Handle<'a> {
     fn control(&self, id: u32) -> Result<control::State> {
         match Control::from_id(id) {
             Some(ctrl) => ctrl.get(&self.inner.handle),
-            None => Err(Error::new(ErrorKind::Other, "unknown control ID")),
+            None => Err(Error::new(ErrorKind::NotFound, "unknown control ID")),
         }
     }
 
@@ -101,26 +109,42 @@ impl<'a> Device<'a> for Handle<'a> {
         Err(Error::from(ErrorKind::NotSupported))
     }
 
+    fn subscribe(&self) -> Result<control::Subscription<'a>> {
+        // libuvc does not report control changes, so they are polled
+        let dev = Handle {
+            inner: self.inner.clone(),
+        };
+        control::Subscription::poll(move || dev.controls())
+    }
+
     fn start_stream(&self, desc: &stream::Descriptor) -> Result<Self::Stream> {
         let dev_handle = self.inner.clone();
         let dev_handle_ptr = &*dev_handle.handle as *const uvc::DeviceHandle;
         let dev_handle_ref = unsafe { &*dev_handle_ptr as &uvc::DeviceHandle };
 
-        let desc_fps = (1.0 / desc.interval.as_secs_f64()) as u64;
-        let stream_format = self.inner.handle.get_preferred_format(|x, y| {
-            if x.width == desc.width && x.height == desc.height && x.fps as u64 >= desc_fps {
-                x
-            } else if x.width == desc.width && x.height == desc.height {
-                x
-            } else {
-                y
-            }
-        });
+        // UVC intervals are rounded to 100 ns, e.g. 333333 for 30 fps
+        let desc_fps = desc.interval.fps().round() as u64;
+        // prefer formats of the right size, then the native format of the stream (e.g. MJPEG),
+        // then a sufficient frame rate
+        let score = |fmt: &uvc::StreamFormat| {
+            (
+                fmt.width == desc.width && fmt.height == desc.height,
+                PixelFormat::try_from(fmt.format).as_ref() == Ok(&desc.pixfmt),
+                fmt.fps as u64 >= desc_fps,
+            )
+        };
+        let stream_format =
+            self.inner
+                .handle
+                .get_preferred_format(|x, y| if score(&x) >= score(&y) { x } else { y });
 
         let stream_format = match stream_format {
             Some(fmt) => {
                 if fmt.width != desc.width || fmt.height != desc.height {
-                    return Err(Error::new(ErrorKind::Other, "invalid stream descriptor"));
+                    return Err(Error::new(
+                        ErrorKind::InvalidArgument,
+                        "invalid stream descriptor",
+                    ));
                 }
 
                 fmt
@@ -130,12 +154,24 @@ impl<'a> Device<'a> for Handle<'a> {
 
         let stream_handle = match dev_handle_ref.get_stream_handle_with_format(stream_format) {
             Ok(handle) => handle,
-            Err(e) => return Err(Error::new(ErrorKind::Other, e)),
+            Err(e) => return Err(Error::from(e)),
         };
 
-        match StreamHandle::new(dev_handle, stream_handle) {
+        // frames are always converted to RGB, see the stream handle
+        let format = stream::Format::packed(&stream::Descriptor {
+            width: stream_format.width,
+            height: stream_format.height,
+            pixfmt: PixelFormat::Rgb(24),
+            interval: if stream_format.fps as u64 == desc_fps {
+                desc.interval
+            } else {
+                stream::Interval::from_fps(stream_format.fps)
+            },
+        });
+
+        match StreamHandle::new(dev_handle, stream_handle, format) {
             Ok(handle) => Ok(handle),
-            Err(e) => Err(Error::new(ErrorKind::Other, e)),
+            Err(e) => Err(Error::from(e)),
         }
     }
 }
@@ -9,3 +9,55 @@ pub mod device;
 pub mod stream;
 
 pub use context::Context;
+
+use std::convert::TryFrom;
+
+use crate::error::{Error, ErrorKind};
+use crate::format::{BayerPattern, PixelFormat};
+
+impl From<uvc::Error> for Error {
+    fn from(error: uvc::Error) -> Self {
+        let kind = match error {
+            uvc::Error::Access => ErrorKind::PermissionDenied,
+            uvc::Error::Busy => ErrorKind::Busy,
+            uvc::Error::NoDevice => ErrorKind::Disconnected,
+            uvc::Error::InvalidParam | uvc::Error::InvalidMode => ErrorKind::InvalidArgument,
+            uvc::Error::NotFound => ErrorKind::NotFound,
+            uvc::Error::NotSupported => ErrorKind::NotSupported,
+            uvc::Error::Timeout => ErrorKind::Timeout,
+            uvc::Error::IO | uvc::Error::Pipe | uvc::Error::Overflow => ErrorKind::Io,
+            _ => ErrorKind::Other,
+        };
+
+        Error::new(kind, error)
+    }
+}
+
+impl TryFrom<uvc::FrameFormat> for PixelFormat {
+    type Error = ();
+
+    fn try_from(format: uvc::FrameFormat) -> Result<Self, Self::Error> {
+        match format {
+            uvc::FrameFormat::YUYV => Ok(PixelFormat::Yuyv(16)),
+            uvc::FrameFormat::UYVY => Ok(PixelFormat::Uyvy(16)),
+            uvc::FrameFormat::RGB => Ok(PixelFormat::Rgb(24)),
+            uvc::FrameFormat::BGR => Ok(PixelFormat::Bgr(24)),
+            uvc::FrameFormat::MJPEG => Ok(PixelFormat::Jpeg),
+            uvc::FrameFormat::GRAY8 => Ok(PixelFormat::Gray(8)),
+            uvc::FrameFormat::GRAY16 => Ok(PixelFormat::Gray(16)),
+            uvc::FrameFormat::BA81 | uvc::FrameFormat::SBGGR8 => {
+                Ok(PixelFormat::Bayer(BayerPattern::Bggr, 8))
+            }
+            uvc::FrameFormat::SGBRG8 => Ok(PixelFormat::Bayer(BayerPattern::Gbrg, 8)),
+            uvc::FrameFormat::SGRBG8 => Ok(PixelFormat::Bayer(BayerPattern::Grbg, 8)),
+            uvc::FrameFormat::SRGGB8 => Ok(PixelFormat::Bayer(BayerPattern::Rggb, 8)),
+            // placeholders which do not describe a particular memory layout
+            uvc::FrameFormat::Unknown
+            | uvc::FrameFormat::Any
+            | uvc::FrameFormat::Uncompressed
+            | uvc::FrameFormat::Compressed
+            | uvc::FrameFormat::BY8
+            | uvc::FrameFormat::Count => Err(()),
+        }
+    }
+}
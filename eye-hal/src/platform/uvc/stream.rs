@@ -1,14 +1,20 @@
 use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
 
-use crate::error::Result;
+use crate::error::{Error, ErrorKind, Result};
 use crate::platform::uvc::device::UvcHandle;
+use crate::stream::{Format, Frame};
 use crate::traits::Stream;
 
+/// Converted frame along with its sequence number and arrival time
+type Item = (u32, Duration, uvc::Result<uvc::Frame>);
+
 pub struct Handle<'a> {
-    rx: mpsc::Receiver<uvc::Result<uvc::Frame>>,
+    rx: mpsc::Receiver<Item>,
+    format: Format,
 
     // these are required to keep the frame callback alive
-    _stream: uvc::ActiveStream<'a, mpsc::SyncSender<uvc::Result<uvc::Frame>>>,
+    _stream: uvc::ActiveStream<'a, (Instant, mpsc::SyncSender<Item>)>,
     _stream_handle: uvc::StreamHandle<'a>,
     _dev_handle: Arc<UvcHandle<'a>>,
 }
@@ -17,15 +23,19 @@ impl<'a> Handle<'a> {
     pub fn new(
         dev_handle: Arc<UvcHandle<'a>>,
         mut stream_handle: uvc::StreamHandle<'a>,
+        format: Format,
     ) -> uvc::Result<Self> {
         let stream_handle_ptr = &mut stream_handle as *mut uvc::StreamHandle;
         let stream_handle_ref = unsafe { &mut *stream_handle_ptr as &mut uvc::StreamHandle };
 
         // establish a rendezvous channel
         let (tx, rx) = mpsc::sync_channel(0);
+        // libuvc does not expose the capture time, so we fall back to the arrival time of the
+        // frame relative to the start of the stream.
+        let epoch = Instant::now();
         let stream = stream_handle_ref.start_stream(
-            |frame, tx| {
-                match tx.send(frame.to_rgb()) {
+            |frame, (epoch, tx)| {
+                match tx.send((frame.sequence(), epoch.elapsed(), frame.to_rgb())) {
                     Ok(()) => {}
                     Err(_) => {
                         // The receiving end hung up.
@@ -33,11 +43,12 @@ impl<'a> Handle<'a> {
                     }
                 }
             },
-            tx,
+            (epoch, tx),
         )?;
 
         Ok(Handle {
             rx,
+            format,
             _stream: stream,
             _stream_handle: stream_handle,
             _dev_handle: dev_handle,
@@ -46,18 +57,39 @@ impl<'a> Handle<'a> {
 }
 
 impl<'a, 'b> Stream<'b> for Handle<'a> {
-    type Item = Result<&'b [u8]>;
+    type Item = Result<Frame<'b>>;
+
+    fn format(&self) -> Format {
+        self.format.clone()
+    }
 
     fn next(&'b mut self) -> Option<Self::Item> {
-        let frame = self.rx.recv().unwrap();
-        let pixels = match &frame {
-            Ok(frame) => frame.to_bytes(),
-            Err(_) => {
-                // The format conversion failed. Pretend the stream died.
-                return None;
-            }
-        };
+        let item = self.rx.recv().unwrap();
+        convert(item)
+    }
 
-        Some(Ok(&pixels))
+    fn next_timeout(&'b mut self, timeout: Duration) -> Option<Self::Item> {
+        match self.rx.recv_timeout(timeout) {
+            Ok(item) => convert(item),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                Some(Err(Error::new(ErrorKind::Timeout, "no frame available")))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => None,
+        }
     }
 }
+
+fn convert<'a>(item: Item) -> Option<Result<Frame<'a>>> {
+    let (sequence, timestamp, frame) = item;
+    let frame = match frame {
+        Ok(frame) => frame,
+        // The format conversion failed, later frames may still be fine.
+        Err(e) => return Some(Err(Error::from(e))),
+    };
+
+    // The converted frame is owned by the channel message, so we have to copy its data.
+    let frame = Frame::new(frame.to_bytes().to_vec())
+        .timestamp(timestamp)
+        .sequence(sequence as u64);
+    Some(Ok(frame))
+}
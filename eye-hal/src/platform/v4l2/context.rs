@@ -1,8 +1,11 @@
+use std::fs;
+
 use v4l::context;
 
 use crate::device;
 use crate::error::{Error, ErrorKind, Result};
 use crate::platform::v4l2::device::Handle as DeviceHandle;
+use crate::platform::v4l2::{hotplug, uri};
 use crate::traits::Context as ContextTrait;
 
 /// Runtime context
@@ -14,35 +17,7 @@ impl<'a> ContextTrait<'a> for Context {
     fn devices(&self) -> Result<Vec<device::Description>> {
         let nodes = context::enum_devices()
             .into_iter()
-            .filter_map(|dev| {
-                let index = dev.index();
-                let dev = match DeviceHandle::new(index) {
-                    Ok(dev) => dev,
-                    Err(_) => return None,
-                };
-
-                let caps = match dev.inner().query_caps() {
-                    Ok(caps) => caps,
-                    Err(_) => return None,
-                };
-
-                // For now, require video capture and streaming capabilities.
-                // Very old devices may only support the read() I/O mechanism, so support for those
-                // might be added in the future. Every recent (released during the last ten to twenty
-                // years) webcam should support streaming though.
-                let capture_flag = v4l::capability::Flags::VIDEO_CAPTURE;
-                let streaming_flag = v4l::capability::Flags::STREAMING;
-                if caps.capabilities & capture_flag != capture_flag
-                    || caps.capabilities & streaming_flag != streaming_flag
-                {
-                    return None;
-                }
-
-                Some(device::Description {
-                    uri: format!("v4l:///dev/video{}", index),
-                    product: caps.card,
-                })
-            })
+            .filter_map(|dev| describe(dev.index()))
             .collect();
 
         Ok(nodes)
@@ -53,7 +28,62 @@ impl<'a> ContextTrait<'a> for Context {
             let handle = crate::platform::v4l2::device::Handle::with_uri(uri)?;
             Ok(handle)
         } else {
-            Err(Error::new(ErrorKind::Other, "invalid URI"))
+            Err(Error::new(ErrorKind::InvalidArgument, "invalid URI"))
         }
     }
+
+    fn watch(&self) -> Result<device::Watcher> {
+        hotplug::watch()
+    }
+}
+
+/// Returns the description of a capture device, or None if the node cannot be used for capturing
+pub(crate) fn describe(index: usize) -> Option<device::Description> {
+    let dev = DeviceHandle::new(index).ok()?;
+    let caps = dev.inner().query_caps().ok()?;
+
+    // For now, require video capture and streaming capabilities.
+    // Very old devices may only support the read() I/O mechanism, so support for those
+    // might be added in the future. Every recent (released during the last ten to twenty
+    // years) webcam should support streaming though.
+    let capture_flag = v4l::capability::Flags::VIDEO_CAPTURE;
+    let streaming_flag = v4l::capability::Flags::STREAMING;
+    if caps.capabilities & capture_flag != capture_flag
+        || caps.capabilities & streaming_flag != streaming_flag
+    {
+        return None;
+    }
+
+    Some(device::Description {
+        uri: uri::stable(index),
+        product: caps.card,
+        backend: "v4l2".to_string(),
+        driver: Some(caps.driver),
+        bus: Some(caps.bus),
+        usb: usb(index),
+    })
+}
+
+/// Reads the USB device information of a video node from sysfs
+pub(crate) fn usb(index: usize) -> Option<device::Usb> {
+    // The device link points to the USB interface, its parent directory is the USB device.
+    let path = format!("/sys/class/video4linux/video{}/device", index);
+    let path = fs::canonicalize(path).ok()?;
+    let path = path.parent()?;
+
+    let read = |name: &str| -> Option<String> {
+        let value = fs::read_to_string(path.join(name)).ok()?;
+        Some(value.trim().to_string())
+    };
+    let hex = |name: &str| u16::from_str_radix(&read(name)?, 16).ok();
+    let dec = |name: &str| read(name)?.parse::<u8>().ok();
+
+    // Non-USB devices (e.g. PCI capture cards or platform cameras) lack these attributes.
+    Some(device::Usb {
+        vendor_id: hex("idVendor")?,
+        product_id: hex("idProduct")?,
+        serial: read("serial").filter(|serial| !serial.is_empty()),
+        bus_number: dec("busnum")?,
+        address: dec("devnum")?,
+    })
 }
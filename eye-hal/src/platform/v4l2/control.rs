@@ -0,0 +1,415 @@
+//! Extended controls
+//!
+//! The simple control API of V4L2 only transports 32 bit values. Everything else, i.e. 64 bit
+//! integers, strings and compound (array) controls, has to go through the extended control API,
+//! which is used for all controls here for the sake of consistency.
+
+use std::convert::TryFrom;
+use std::os::raw::c_void;
+use std::{io, mem};
+
+use v4l::control::Flags as ControlFlags;
+use v4l::device::Handle;
+use v4l::v4l2;
+use v4l::v4l_sys::{
+    v4l2_ctrl_type_V4L2_CTRL_TYPE_BITMASK, v4l2_ctrl_type_V4L2_CTRL_TYPE_BOOLEAN,
+    v4l2_ctrl_type_V4L2_CTRL_TYPE_BUTTON, v4l2_ctrl_type_V4L2_CTRL_TYPE_CTRL_CLASS,
+    v4l2_ctrl_type_V4L2_CTRL_TYPE_INTEGER, v4l2_ctrl_type_V4L2_CTRL_TYPE_INTEGER64,
+    v4l2_ctrl_type_V4L2_CTRL_TYPE_INTEGER_MENU, v4l2_ctrl_type_V4L2_CTRL_TYPE_MENU,
+    v4l2_ctrl_type_V4L2_CTRL_TYPE_STRING, v4l2_ctrl_type_V4L2_CTRL_TYPE_U16,
+    v4l2_ctrl_type_V4L2_CTRL_TYPE_U32, v4l2_ctrl_type_V4L2_CTRL_TYPE_U8, v4l2_ext_control,
+    v4l2_ext_controls, v4l2_query_ext_ctrl, V4L2_CTRL_FLAG_DISABLED, V4L2_CTRL_FLAG_HAS_PAYLOAD,
+    V4L2_CTRL_FLAG_NEXT_COMPOUND, V4L2_CTRL_FLAG_NEXT_CTRL,
+};
+
+use crate::control::{Flags, State};
+use crate::error::{Error, ErrorKind, Result};
+
+// Control types, shortened from the names bindgen derives from enum v4l2_ctrl_type
+pub const V4L2_CTRL_TYPE_INTEGER: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_INTEGER;
+pub const V4L2_CTRL_TYPE_BOOLEAN: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_BOOLEAN;
+pub const V4L2_CTRL_TYPE_MENU: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_MENU;
+pub const V4L2_CTRL_TYPE_BUTTON: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_BUTTON;
+pub const V4L2_CTRL_TYPE_INTEGER64: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_INTEGER64;
+pub const V4L2_CTRL_TYPE_CTRL_CLASS: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_CTRL_CLASS;
+pub const V4L2_CTRL_TYPE_STRING: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_STRING;
+pub const V4L2_CTRL_TYPE_BITMASK: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_BITMASK;
+pub const V4L2_CTRL_TYPE_INTEGER_MENU: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_INTEGER_MENU;
+pub const V4L2_CTRL_TYPE_U8: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_U8;
+pub const V4L2_CTRL_TYPE_U16: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_U16;
+pub const V4L2_CTRL_TYPE_U32: u32 = v4l2_ctrl_type_V4L2_CTRL_TYPE_U32;
+
+/// Returns the extended description of a control
+///
+/// # Arguments
+///
+/// * `handle` - Device handle
+/// * `id` - Control identifier
+pub fn query(handle: &Handle, id: u32) -> io::Result<v4l2_query_ext_ctrl> {
+    let mut query = v4l2_query_ext_ctrl {
+        id,
+        ..unsafe { mem::zeroed() }
+    };
+    unsafe {
+        v4l2::ioctl(
+            handle.fd(),
+            v4l2::vidioc::VIDIOC_QUERY_EXT_CTRL,
+            &mut query as *mut _ as *mut c_void,
+        )?;
+    }
+
+    Ok(query)
+}
+
+/// Returns the identifiers of all controls which are not permanently disabled
+///
+/// Unlike [`v4l::Device::query_controls`], this neither enumerates menu items nor reads values.
+///
+/// # Arguments
+///
+/// * `handle` - Device handle
+pub fn ids(handle: &Handle) -> io::Result<Vec<u32>> {
+    let mut ids = Vec::new();
+    let mut query: v4l2_query_ext_ctrl = unsafe { mem::zeroed() };
+
+    loop {
+        query.id |= V4L2_CTRL_FLAG_NEXT_CTRL | V4L2_CTRL_FLAG_NEXT_COMPOUND;
+        let res = unsafe {
+            v4l2::ioctl(
+                handle.fd(),
+                v4l2::vidioc::VIDIOC_QUERY_EXT_CTRL,
+                &mut query as *mut _ as *mut c_void,
+            )
+        };
+        match res {
+            Ok(()) => {}
+            // there are no more controls
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => return Ok(ids),
+            Err(e) => return Err(e),
+        }
+
+        // control classes only group the controls that follow them
+        if query.flags & V4L2_CTRL_FLAG_DISABLED == 0 && query.type_ != V4L2_CTRL_TYPE_CTRL_CLASS {
+            ids.push(query.id);
+        }
+    }
+}
+
+/// Returns true if the control value is passed through a pointer
+///
+/// # Arguments
+///
+/// * `query` - Extended control description
+pub fn has_payload(query: &v4l2_query_ext_ctrl) -> bool {
+    query.flags & V4L2_CTRL_FLAG_HAS_PAYLOAD == V4L2_CTRL_FLAG_HAS_PAYLOAD
+}
+
+/// Returns the access and state flags of a control
+///
+/// # Arguments
+///
+/// * `flags` - V4L2 control flags
+pub fn flags<F: Into<ControlFlags>>(flags: F) -> Flags {
+    let v4l_flags = flags.into();
+    // assume controls to be readable and writable by default
+    let mut flags = Flags::READ | Flags::WRITE;
+
+    if v4l_flags & ControlFlags::READ_ONLY == ControlFlags::READ_ONLY {
+        flags.remove(Flags::WRITE);
+        flags.insert(Flags::READ);
+    }
+    if v4l_flags & ControlFlags::WRITE_ONLY == ControlFlags::WRITE_ONLY {
+        flags.remove(Flags::READ);
+        flags.insert(Flags::WRITE);
+    }
+    if v4l_flags & ControlFlags::GRABBED == ControlFlags::GRABBED {
+        flags.remove(Flags::WRITE);
+    }
+    if v4l_flags & ControlFlags::INACTIVE == ControlFlags::INACTIVE {
+        flags.insert(Flags::INACTIVE);
+    }
+
+    flags
+}
+
+/// Returns the default value of a control
+///
+/// Only controls holding a single number report a default value.
+///
+/// # Arguments
+///
+/// * `query` - Extended control description
+pub fn default(query: &v4l2_query_ext_ctrl) -> Option<State> {
+    if has_payload(query) {
+        return None;
+    }
+
+    let value = query.default_value;
+    match query.type_ {
+        V4L2_CTRL_TYPE_INTEGER => Some(State::Number(value as f64)),
+        V4L2_CTRL_TYPE_INTEGER64 => Some(State::Integer64(value)),
+        V4L2_CTRL_TYPE_BOOLEAN => Some(State::Boolean(value != 0)),
+        V4L2_CTRL_TYPE_MENU | V4L2_CTRL_TYPE_INTEGER_MENU => Some(State::MenuIndex(value as u32)),
+        V4L2_CTRL_TYPE_BITMASK => Some(State::Bitmask(value as u32)),
+        _ => None,
+    }
+}
+
+/// Reads the current value of a control
+///
+/// # Arguments
+///
+/// * `handle` - Device handle
+/// * `query` - Extended control description
+pub fn read(handle: &Handle, query: &v4l2_query_ext_ctrl) -> Result<State> {
+    let mut control = v4l2_ext_control {
+        id: query.id,
+        ..unsafe { mem::zeroed() }
+    };
+
+    if !has_payload(query) {
+        ioctl(handle, v4l2::vidioc::VIDIOC_G_EXT_CTRLS, &mut control)?;
+
+        let (value, value64) = unsafe {
+            (
+                control.__bindgen_anon_1.value,
+                control.__bindgen_anon_1.value64,
+            )
+        };
+        return match query.type_ {
+            V4L2_CTRL_TYPE_INTEGER => Ok(State::Number(value as f64)),
+            V4L2_CTRL_TYPE_INTEGER64 => Ok(State::Integer64(value64)),
+            V4L2_CTRL_TYPE_BOOLEAN => Ok(State::Boolean(value != 0)),
+            V4L2_CTRL_TYPE_MENU | V4L2_CTRL_TYPE_INTEGER_MENU => Ok(State::MenuIndex(value as u32)),
+            V4L2_CTRL_TYPE_BITMASK => Ok(State::Bitmask(value as u32)),
+            V4L2_CTRL_TYPE_BUTTON => Ok(State::None),
+            _ => Err(Error::new(
+                ErrorKind::NotSupported,
+                "control type is not supported",
+            )),
+        };
+    }
+
+    let mut buf = vec![0u8; query.elem_size as usize * query.elems as usize];
+    control.size = buf.len() as u32;
+    control.__bindgen_anon_1.ptr = buf.as_mut_ptr() as *mut c_void;
+    ioctl(handle, v4l2::vidioc::VIDIOC_G_EXT_CTRLS, &mut control)?;
+
+    let elem_size = query.elem_size as usize;
+    let state = match query.type_ {
+        V4L2_CTRL_TYPE_STRING => {
+            // strings are NUL-terminated within the buffer
+            let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+            State::String(String::from_utf8_lossy(&buf[..len]).into_owned())
+        }
+        V4L2_CTRL_TYPE_U8
+        | V4L2_CTRL_TYPE_U16
+        | V4L2_CTRL_TYPE_U32
+        | V4L2_CTRL_TYPE_INTEGER
+        | V4L2_CTRL_TYPE_INTEGER64
+        | V4L2_CTRL_TYPE_BOOLEAN => State::Array(
+            buf.chunks_exact(elem_size)
+                .map(|elem| decode(query.type_, elem))
+                .collect(),
+        ),
+        _ => State::Compound(buf),
+    };
+
+    Ok(state)
+}
+
+/// Writes the value of a control
+///
+/// # Arguments
+///
+/// * `handle` - Device handle
+/// * `query` - Extended control description
+/// * `state` - New value, must match the type of the control
+pub fn write(handle: &Handle, query: &v4l2_query_ext_ctrl, state: &State) -> Result<()> {
+    let mut control = v4l2_ext_control {
+        id: query.id,
+        ..unsafe { mem::zeroed() }
+    };
+
+    if !has_payload(query) {
+        match (query.type_, state) {
+            (V4L2_CTRL_TYPE_INTEGER64, State::Integer64(val)) => {
+                control.__bindgen_anon_1.value64 = *val
+            }
+            (V4L2_CTRL_TYPE_INTEGER64, State::Number(val)) => {
+                control.__bindgen_anon_1.value64 = integer(*val)?
+            }
+            (V4L2_CTRL_TYPE_INTEGER | V4L2_CTRL_TYPE_BOOLEAN, State::Number(val)) => {
+                control.__bindgen_anon_1.value = integer::<i32>(*val)?
+            }
+            (
+                V4L2_CTRL_TYPE_MENU | V4L2_CTRL_TYPE_INTEGER_MENU | V4L2_CTRL_TYPE_BITMASK,
+                State::Number(val),
+            ) => control.__bindgen_anon_1.value = integer::<u32>(*val)? as i32,
+            (V4L2_CTRL_TYPE_MENU | V4L2_CTRL_TYPE_INTEGER_MENU, State::MenuIndex(index)) => {
+                control.__bindgen_anon_1.value = *index as i32
+            }
+            (V4L2_CTRL_TYPE_BITMASK, State::Bitmask(bits)) => {
+                control.__bindgen_anon_1.value = *bits as i32
+            }
+            (V4L2_CTRL_TYPE_BOOLEAN, State::Boolean(val)) => {
+                control.__bindgen_anon_1.value = *val as i32
+            }
+            (V4L2_CTRL_TYPE_BUTTON, State::None) => {}
+            _ => return Err(mismatch()),
+        }
+
+        return ioctl(handle, v4l2::vidioc::VIDIOC_S_EXT_CTRLS, &mut control);
+    }
+
+    let elem_size = query.elem_size as usize;
+    let mut buf = match (query.type_, state) {
+        (V4L2_CTRL_TYPE_STRING, State::String(val)) => {
+            // leave room for the terminating NUL
+            if val.len() >= elem_size {
+                return Err(Error::new(
+                    ErrorKind::InvalidArgument,
+                    format!("string exceeds {} bytes", elem_size - 1),
+                ));
+            }
+            let mut buf = val.as_bytes().to_vec();
+            buf.push(0);
+            buf
+        }
+        (_, State::Array(values)) if values.len() == query.elems as usize => {
+            let mut buf = Vec::with_capacity(elem_size * values.len());
+            for value in values {
+                buf.extend_from_slice(&encode(*value, elem_size).ok_or_else(mismatch)?);
+            }
+            buf
+        }
+        (_, State::Compound(bytes)) if bytes.len() == elem_size * query.elems as usize => {
+            bytes.clone()
+        }
+        _ => return Err(mismatch()),
+    };
+
+    control.size = buf.len() as u32;
+    control.__bindgen_anon_1.ptr = buf.as_mut_ptr() as *mut c_void;
+    ioctl(handle, v4l2::vidioc::VIDIOC_S_EXT_CTRLS, &mut control)
+}
+
+/// Decodes a single array element stored in native byte order
+fn decode(typ: u32, elem: &[u8]) -> i64 {
+    match (typ, elem.len()) {
+        (V4L2_CTRL_TYPE_INTEGER | V4L2_CTRL_TYPE_BOOLEAN, 4) => {
+            i32::from_ne_bytes([elem[0], elem[1], elem[2], elem[3]]) as i64
+        }
+        (_, 1) => elem[0] as i64,
+        (_, 2) => u16::from_ne_bytes([elem[0], elem[1]]) as i64,
+        (_, 4) => u32::from_ne_bytes([elem[0], elem[1], elem[2], elem[3]]) as i64,
+        (_, 8) => {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(elem);
+            i64::from_ne_bytes(bytes)
+        }
+        _ => 0,
+    }
+}
+
+/// Encodes a single array element in native byte order
+fn encode(value: i64, elem_size: usize) -> Option<Vec<u8>> {
+    match elem_size {
+        1 => Some((value as u8).to_ne_bytes().to_vec()),
+        2 => Some((value as u16).to_ne_bytes().to_vec()),
+        4 => Some((value as u32).to_ne_bytes().to_vec()),
+        8 => Some(value.to_ne_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+/// Transfers a single control through the extended control API
+fn ioctl(
+    handle: &Handle,
+    request: v4l2::vidioc::_IOC_TYPE,
+    control: &mut v4l2_ext_control,
+) -> Result<()> {
+    // Zeroing selects V4L2_CTRL_WHICH_CUR_VAL, i.e. the current value of the control.
+    let mut controls = v4l2_ext_controls {
+        count: 1,
+        controls: control as *mut _,
+        ..unsafe { mem::zeroed() }
+    };
+    unsafe {
+        v4l2::ioctl(handle.fd(), request, &mut controls as *mut _ as *mut c_void)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the error for values which do not fit the control
+/// Converts a number to an integer type, numbers which are not exactly representable are rejected
+///
+/// # Arguments
+///
+/// * `val` - Number
+fn integer<T: TryFrom<i64>>(val: f64) -> Result<T> {
+    // i64::MAX is not representable as f64, the bound is rounded up to 2^63
+    let exact = val.fract() == 0.0 && val >= i64::MIN as f64 && val < i64::MAX as f64;
+    if exact {
+        if let Ok(val) = T::try_from(val as i64) {
+            return Ok(val);
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::InvalidArgument,
+        format!("control value out of range: {}", val),
+    ))
+}
+
+fn mismatch() -> Error {
+    Error::new(
+        ErrorKind::InvalidArgument,
+        "control value does not match the control type",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inactive_flags() {
+        let flags = flags(ControlFlags::INACTIVE);
+        assert_eq!(flags, Flags::READ | Flags::WRITE | Flags::INACTIVE);
+    }
+
+    #[test]
+    fn inactive_read_only_flags() {
+        let flags = flags(ControlFlags::INACTIVE | ControlFlags::READ_ONLY);
+        assert_eq!(flags, Flags::READ | Flags::INACTIVE);
+    }
+
+    #[test]
+    fn exact_integers() {
+        assert_eq!(integer::<i32>(-5.0).unwrap(), -5);
+        assert_eq!(integer::<i64>(-(2f64.powi(63))).unwrap(), i64::MIN);
+        assert_eq!(integer::<u32>(4294967295.0).unwrap(), u32::MAX);
+
+        for val in [1.5, f64::NAN, f64::INFINITY, 2f64.powi(31)] {
+            let err = integer::<i32>(val).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+        }
+        assert!(integer::<i64>(2f64.powi(63)).is_err());
+        assert!(integer::<u32>(-1.0).is_err());
+    }
+
+    #[test]
+    fn integer_defaults() {
+        let mut query: v4l2_query_ext_ctrl = unsafe { mem::zeroed() };
+        query.type_ = V4L2_CTRL_TYPE_INTEGER;
+        query.default_value = -5;
+        assert_eq!(default(&query), Some(State::Number(-5.0)));
+
+        // 64 bit integers are kept exact
+        query.type_ = V4L2_CTRL_TYPE_INTEGER64;
+        query.default_value = i64::MAX;
+        assert_eq!(default(&query), Some(State::Integer64(i64::MAX)));
+    }
+}
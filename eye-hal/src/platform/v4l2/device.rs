@@ -1,7 +1,15 @@
-use std::{convert::TryInto, io, path::Path, time::Duration};
+use std::{convert::TryFrom, io, path::Path};
 
-use v4l::control::{
-    Control, MenuItem as ControlMenuItem, Type as ControlType, Value as ControlValue,
+use v4l::control::{MenuItem as ControlMenuItem, Type as ControlType};
+use v4l::frameinterval::FrameIntervalEnum;
+use v4l::framesize::FrameSizeEnum;
+use v4l::v4l_sys::{
+    V4L2_CID_AUTOGAIN, V4L2_CID_AUTO_WHITE_BALANCE, V4L2_CID_BACKLIGHT_COMPENSATION,
+    V4L2_CID_BRIGHTNESS, V4L2_CID_CONTRAST, V4L2_CID_EXPOSURE, V4L2_CID_EXPOSURE_ABSOLUTE,
+    V4L2_CID_EXPOSURE_AUTO, V4L2_CID_FOCUS_ABSOLUTE, V4L2_CID_FOCUS_AUTO, V4L2_CID_GAIN,
+    V4L2_CID_GAMMA, V4L2_CID_HUE, V4L2_CID_PAN_ABSOLUTE, V4L2_CID_POWER_LINE_FREQUENCY,
+    V4L2_CID_SATURATION, V4L2_CID_SHARPNESS, V4L2_CID_TILT_ABSOLUTE,
+    V4L2_CID_WHITE_BALANCE_TEMPERATURE, V4L2_CID_ZOOM_ABSOLUTE,
 };
 use v4l::video::Capture;
 use v4l::Device as CaptureDevice;
@@ -10,9 +18,14 @@ use v4l::FourCC as FourCC_;
 
 use crate::control;
 use crate::error::{Error, ErrorKind, Result};
-use crate::format::PixelFormat;
+use crate::format::{FourCC, ImageFormat, PixelFormat};
+use crate::platform::v4l2::control as v4l_ctrl;
+use crate::platform::v4l2::event;
 use crate::platform::v4l2::stream::Handle as StreamHandle;
-use crate::stream::Descriptor as StreamDescriptor;
+use crate::platform::v4l2::uri;
+use crate::stream::{
+    Bounds, Descriptor as StreamDescriptor, Format as StreamFormat, Interval, Range as StreamRange,
+};
 use crate::traits::Device;
 
 pub struct Handle {
@@ -28,12 +41,8 @@ impl Handle {
     }
 
     pub fn with_uri<S: Into<String>>(uri: S) -> io::Result<Self> {
-        let uri = uri.into();
-        if let Some(path) = uri.strip_prefix("v4l://") {
-            Self::with_path(path)
-        } else {
-            Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid URI"))
-        }
+        let path = uri::resolve(&uri.into())?;
+        Self::with_path(path)
     }
 
     pub fn with_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
@@ -49,39 +58,70 @@ impl Handle {
 }
 
 impl<'a> Device<'a> for Handle {
-    type Stream = StreamHandle<'a>;
+    type Stream = StreamHandle;
 
     fn streams(&self) -> Result<Vec<StreamDescriptor>> {
-        let mut streams = Vec::new();
+        let streams = self
+            .stream_ranges()?
+            .iter()
+            .flat_map(StreamRange::descriptors)
+            .collect();
+
+        Ok(streams)
+    }
+
+    fn stream_ranges(&self) -> Result<Vec<StreamRange>> {
+        let mut ranges = Vec::new();
         let plat_formats = self.inner.enum_formats()?;
 
         for format in plat_formats {
+            let pixfmt = PixelFormat::from(FourCC::new(&format.fourcc.repr));
+
             for framesize in self.inner.enum_framesizes(format.fourcc)? {
-                // TODO: consider stepwise formats
-                if let v4l::framesize::FrameSizeEnum::Discrete(size) = framesize.size {
-                    for frameinterval in
-                        self.inner
-                            .enum_frameintervals(format.fourcc, size.width, size.height)?
-                    {
-                        // TODO: consider stepwise intervals
-                        if let v4l::frameinterval::FrameIntervalEnum::Discrete(fraction) =
-                            frameinterval.interval
-                        {
-                            streams.push(StreamDescriptor {
-                                width: size.width,
-                                height: size.height,
-                                pixfmt: PixelFormat::from(&format.fourcc.repr),
-                                interval: Duration::from_secs_f64(
-                                    fraction.numerator as f64 / fraction.denominator as f64,
-                                ),
-                            });
-                        }
+                let (width, height) = match framesize.size {
+                    FrameSizeEnum::Discrete(size) => {
+                        (Bounds::fixed(size.width), Bounds::fixed(size.height))
                     }
+                    FrameSizeEnum::Stepwise(size) => (
+                        Bounds {
+                            min: size.min_width,
+                            max: size.max_width,
+                            step: size.step_width,
+                        },
+                        Bounds {
+                            min: size.min_height,
+                            max: size.max_height,
+                            step: size.step_height,
+                        },
+                    ),
+                };
+
+                // Intervals are enumerated per frame size. For frame size ranges, the largest
+                // size is the most restrictive one.
+                for frameinterval in
+                    self.inner
+                        .enum_frameintervals(format.fourcc, width.max, height.max)?
+                {
+                    let interval = match frameinterval.interval {
+                        FrameIntervalEnum::Discrete(fraction) => Bounds::fixed(interval(&fraction)),
+                        FrameIntervalEnum::Stepwise(stepwise) => Bounds {
+                            min: interval(&stepwise.min),
+                            max: interval(&stepwise.max),
+                            step: interval(&stepwise.step),
+                        },
+                    };
+
+                    ranges.push(StreamRange {
+                        pixfmt: pixfmt.clone(),
+                        width,
+                        height,
+                        interval,
+                    });
                 }
             }
         }
 
-        Ok(streams)
+        Ok(ranges)
     }
 
     fn controls(&self) -> Result<Vec<control::Descriptor>> {
@@ -94,25 +134,34 @@ impl<'a> Device<'a> for Handle {
                 continue;
             }
 
+            let query = match v4l_ctrl::query(&self.inner.handle(), control.id) {
+                Ok(query) => query,
+                Err(_) => continue,
+            };
+
             let state_type = match control.typ {
+                // arrays and structures, strings are passed through a pointer as well
+                _ if query.nr_of_dims > 0
+                    || (v4l_ctrl::has_payload(&query) && control.typ != ControlType::String) =>
+                {
+                    control::Type::Compound
+                }
                 ControlType::Integer | ControlType::Integer64 => control::Type::Number {
                     range: (control.minimum as f64, control.maximum as f64),
                     step: control.step as f32,
                 },
                 ControlType::Boolean => control::Type::Boolean,
-                ControlType::Menu => {
+                ControlType::Menu | ControlType::IntegerMenu => {
                     let mut items = Vec::new();
-                    if let Some(plat_items) = control.items {
-                        for plat_item in plat_items {
-                            match plat_item.1 {
-                                ControlMenuItem::Name(name) => {
-                                    items.push(control::MenuItem::String(name));
-                                }
-                                ControlMenuItem::Value(value) => {
-                                    items.push(control::MenuItem::Number(value as f64));
-                                }
+                    // keep the indices, drivers may skip some of them
+                    for (index, item) in control.items.unwrap_or_default() {
+                        let item = match item {
+                            ControlMenuItem::Name(name) => control::MenuItem::String(name),
+                            ControlMenuItem::Value(value) => {
+                                control::MenuItem::Number(value as f64)
                             }
-                        }
+                        };
+                        items.push((index, item));
                     }
                     control::Type::Menu(items)
                 }
@@ -122,30 +171,23 @@ impl<'a> Device<'a> for Handle {
                 _ => continue,
             };
 
-            // assume controls to be readable and writable by default
-            let mut flags = control::Flags::READ | control::Flags::WRITE;
+            let flags = v4l_ctrl::flags(control.flags);
 
-            if control.flags & v4l::control::Flags::READ_ONLY == v4l::control::Flags::READ_ONLY {
-                flags.remove(control::Flags::WRITE);
-                flags.insert(control::Flags::READ);
-            }
-            if control.flags & v4l::control::Flags::WRITE_ONLY == v4l::control::Flags::WRITE_ONLY {
-                flags.remove(control::Flags::READ);
-                flags.insert(control::Flags::WRITE);
-            }
-            if control.flags & v4l::control::Flags::GRABBED == v4l::control::Flags::GRABBED {
-                flags.remove(control::Flags::WRITE);
-            }
-            if control.flags & v4l::control::Flags::INACTIVE == v4l::control::Flags::INACTIVE {
-                flags.remove(control::Flags::READ);
-                flags.remove(control::Flags::WRITE);
-            }
+            let default = v4l_ctrl::default(&query);
+            let value = if flags.contains(control::Flags::READ) {
+                v4l_ctrl::read(&self.inner.handle(), &query).ok()
+            } else {
+                None
+            };
 
             controls.push(control::Descriptor {
                 id: control.id,
                 name: control.name,
+                kind: kind(control.id),
                 typ: state_type,
                 flags,
+                default,
+                value,
             })
         }
 
@@ -153,55 +195,112 @@ impl<'a> Device<'a> for Handle {
     }
 
     fn control(&self, id: u32) -> Result<control::State> {
-        let ctrl = self.inner.control(id)?;
-        match ctrl.value {
-            ControlValue::Integer(val) => Ok(control::State::Number(val as f64)),
-            ControlValue::Boolean(val) => Ok(control::State::Boolean(val)),
-            _ => Err(Error::new(
-                ErrorKind::Other,
-                "control value cannot be mapped",
-            )),
-        }
+        let handle = self.inner.handle();
+        let query = v4l_ctrl::query(&handle, id)?;
+        v4l_ctrl::read(&handle, &query)
     }
 
     fn set_control(&mut self, id: u32, val: &control::State) -> Result<()> {
-        let value = match val {
-            control::State::Number(val) => ControlValue::Integer(*val as i64),
-            control::State::Boolean(val) => ControlValue::Boolean(*val),
-            _ => {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "control value cannot be mapped",
-                ))
-            }
-        };
+        let handle = self.inner.handle();
+        let query = v4l_ctrl::query(&handle, id)?;
+        v4l_ctrl::write(&handle, &query, val)
+    }
 
-        let ctrl = Control { id, value };
-        self.inner.set_control(ctrl)?;
+    fn control_ids(&self) -> Result<Vec<(u32, Option<control::Kind>)>> {
+        let ids = v4l_ctrl::ids(&self.inner.handle())?;
+        Ok(ids.into_iter().map(|id| (id, kind(id))).collect())
+    }
 
-        Ok(())
+    fn subscribe(&self) -> Result<control::Subscription<'a>> {
+        let ids = v4l_ctrl::ids(&self.inner.handle())?;
+        event::subscribe(&self.inner, &ids)
     }
 
     fn start_stream(&self, desc: &StreamDescriptor) -> Result<Self::Stream> {
-        let fourcc = if let Ok(fourcc) = desc.pixfmt.clone().try_into() {
-            fourcc
-        } else {
+        let fourcc = FourCC::try_from(&desc.pixfmt)?;
+        // configure frame format
+        let format = CaptureFormat::new(desc.width, desc.height, FourCC_::new(&fourcc.repr));
+        let format = self.inner.set_format(&format)?;
+
+        // Drivers pick the closest format they support instead of failing, so a mismatch means
+        // the frames would have to be interpreted differently than requested.
+        if format.width != desc.width
+            || format.height != desc.height
+            || format.fourcc != FourCC_::new(&fourcc.repr)
+        {
             return Err(Error::new(
-                ErrorKind::Other,
-                "failed to map pixelformat to fourcc",
+                ErrorKind::UnsupportedFormat,
+                format!(
+                    "driver substituted {}x{} {} for {}x{} {}",
+                    format.width,
+                    format.height,
+                    PixelFormat::from(FourCC::new(&format.fourcc.repr)),
+                    desc.width,
+                    desc.height,
+                    desc.pixfmt
+                ),
             ));
-        };
-        // configure frame format
-        let format = CaptureFormat::new(desc.width, desc.height, FourCC_::new(&fourcc));
-        self.inner.set_format(&format)?;
+        }
 
-        // configure frame timing
-        let fps = (1.0 / desc.interval.as_secs_f32()) as u32;
+        // configure frame timing, drivers round the interval to the closest one they support
         let mut params = self.inner.params()?;
-        params.interval = v4l::Fraction::new(1, fps);
-        self.inner.set_params(&params)?;
+        params.interval = v4l::Fraction::new(desc.interval.numerator, desc.interval.denominator);
+        let params = self.inner.set_params(&params)?;
+        let interval = match params.interval.denominator {
+            // the driver does not report the interval it uses
+            0 => desc.interval,
+            _ => interval(&params.interval),
+        };
 
-        let handle = StreamHandle::new(self)?;
+        // Drivers may pad rows (bytesperline) for alignment, so the layout has to be derived
+        // from the stride they report instead of the frame width.
+        let mut image = ImageFormat::new(format.width, format.height, desc.pixfmt.clone());
+        if format.stride > 0 {
+            image = image.stride(format.stride as usize);
+        }
+
+        let format = StreamFormat {
+            width: format.width,
+            height: format.height,
+            pixfmt: desc.pixfmt.clone(),
+            stride: format.stride as usize,
+            size: format.size as usize,
+            planes: image.planes,
+            interval,
+        };
+
+        let handle = StreamHandle::new(self, format)?;
         Ok(handle)
     }
 }
+
+fn interval(fraction: &v4l::Fraction) -> Interval {
+    Interval::new(fraction.numerator, fraction.denominator)
+}
+
+/// Returns the well-known function of a control
+fn kind(id: u32) -> Option<control::Kind> {
+    match id {
+        V4L2_CID_BRIGHTNESS => Some(control::Kind::Brightness),
+        V4L2_CID_CONTRAST => Some(control::Kind::Contrast),
+        V4L2_CID_SATURATION => Some(control::Kind::Saturation),
+        V4L2_CID_HUE => Some(control::Kind::Hue),
+        V4L2_CID_AUTO_WHITE_BALANCE => Some(control::Kind::AutoWhiteBalance),
+        V4L2_CID_GAMMA => Some(control::Kind::Gamma),
+        V4L2_CID_EXPOSURE => Some(control::Kind::Exposure),
+        V4L2_CID_AUTOGAIN => Some(control::Kind::AutoGain),
+        V4L2_CID_GAIN => Some(control::Kind::Gain),
+        V4L2_CID_POWER_LINE_FREQUENCY => Some(control::Kind::PowerLineFrequency),
+        V4L2_CID_WHITE_BALANCE_TEMPERATURE => Some(control::Kind::WhiteBalance),
+        V4L2_CID_SHARPNESS => Some(control::Kind::Sharpness),
+        V4L2_CID_BACKLIGHT_COMPENSATION => Some(control::Kind::BacklightCompensation),
+        V4L2_CID_EXPOSURE_AUTO => Some(control::Kind::AutoExposure),
+        V4L2_CID_EXPOSURE_ABSOLUTE => Some(control::Kind::Exposure),
+        V4L2_CID_PAN_ABSOLUTE => Some(control::Kind::Pan),
+        V4L2_CID_TILT_ABSOLUTE => Some(control::Kind::Tilt),
+        V4L2_CID_FOCUS_ABSOLUTE => Some(control::Kind::Focus),
+        V4L2_CID_FOCUS_AUTO => Some(control::Kind::AutoFocus),
+        V4L2_CID_ZOOM_ABSOLUTE => Some(control::Kind::Zoom),
+        _ => None,
+    }
+}
@@ -0,0 +1,109 @@
+//! Control change events
+//!
+//! Events are queued per file handle and a file handle is not notified about changes it made
+//! itself. The device node is therefore opened once more for every subscription, which then
+//! receives the changes made through the device handle as well.
+
+use std::mem;
+use std::os::raw::c_void;
+
+use v4l::v4l2;
+use v4l::v4l_sys::{v4l2_event, v4l2_event_subscription, V4L2_EVENT_CTRL};
+use v4l::Device as CaptureDevice;
+
+use crate::control;
+use crate::error::{Error, ErrorKind, Result};
+use crate::platform::v4l2::control as v4l_ctrl;
+
+// Event ioctls, which are not provided by the v4l crate
+const VIDIOC_DQEVENT: v4l2::vidioc::_IOC_TYPE = ioc(2, 89, mem::size_of::<v4l2_event>());
+const VIDIOC_SUBSCRIBE_EVENT: v4l2::vidioc::_IOC_TYPE =
+    ioc(1, 90, mem::size_of::<v4l2_event_subscription>());
+
+/// Encodes an ioctl request number as defined in linux/ioctl.h
+const fn ioc(dir: u32, nr: u32, size: usize) -> v4l2::vidioc::_IOC_TYPE {
+    ((dir << 30) | ((size as u32) << 16) | ((b'V' as u32) << 8) | nr) as v4l2::vidioc::_IOC_TYPE
+}
+
+/// Subscribes to changes of controls
+///
+/// # Arguments
+///
+/// * `dev` - Device handle
+/// * `ids` - Identifiers of the controls to watch
+pub fn subscribe<'a>(dev: &CaptureDevice, ids: &[u32]) -> Result<control::Subscription<'a>> {
+    let dev = CaptureDevice::with_path(format!("/proc/self/fd/{}", dev.handle().fd()))?;
+
+    for id in ids {
+        let mut sub = v4l2_event_subscription {
+            type_: V4L2_EVENT_CTRL,
+            id: *id,
+            ..unsafe { mem::zeroed() }
+        };
+        unsafe {
+            v4l2::ioctl(
+                dev.handle().fd(),
+                VIDIOC_SUBSCRIBE_EVENT,
+                &mut sub as *mut _ as *mut c_void,
+            )?;
+        }
+    }
+
+    Ok(control::Subscription::new(move |timeout| {
+        // drain the queue before waiting for more events
+        let mut waited = false;
+        loop {
+            match dequeue(&dev) {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) if waited => {
+                    return Some(Err(Error::new(ErrorKind::Timeout, "no event available")))
+                }
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            // Pending events are signaled as exceptional conditions.
+            let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+            if let Err(e) = dev.handle().poll(libc::POLLPRI, timeout) {
+                return Some(Err(e.into()));
+            }
+            waited = true;
+        }
+    }))
+}
+
+/// Returns the next pending event without blocking
+fn dequeue(dev: &CaptureDevice) -> Result<Option<control::Event>> {
+    let mut event: v4l2_event = unsafe { mem::zeroed() };
+    let res = unsafe {
+        v4l2::ioctl(
+            dev.handle().fd(),
+            VIDIOC_DQEVENT,
+            &mut event as *mut _ as *mut c_void,
+        )
+    };
+    match res {
+        Ok(()) => {}
+        // no event pending
+        Err(e) if e.raw_os_error() == Some(libc::ENOENT) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    // The event carries the new value, but only for controls which hold a single number.
+    let flags = v4l_ctrl::flags(unsafe { event.u.ctrl.flags });
+    let value = if flags.contains(control::Flags::READ) {
+        let handle = dev.handle();
+        v4l_ctrl::query(&handle, event.id)
+            .map_err(Error::from)
+            .and_then(|query| v4l_ctrl::read(&handle, &query))
+            .ok()
+    } else {
+        None
+    };
+
+    Ok(Some(control::Event::ControlChanged {
+        id: event.id,
+        value,
+        flags,
+    }))
+}
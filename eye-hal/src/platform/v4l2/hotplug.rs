@@ -0,0 +1,161 @@
+//! Hotplug monitoring
+//!
+//! Device nodes are watched through inotify, so no device has to be opened unless it was just
+//! plugged in.
+//!
+//! Devices are announced as soon as their node is accessible. At that point, udev may not have
+//! created the persistent symlinks yet, in which case the announced URI refers to the node itself.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::ffi::CString;
+use std::{io, mem, thread};
+
+use v4l::context;
+
+use crate::device::{Event, Notifier, Watcher};
+use crate::error::Result;
+use crate::platform::v4l2::context::describe;
+
+/// Directory containing the device nodes
+const DEV_DIR: &str = "/dev";
+
+/// Time to wait for inotify events before checking whether the watcher is still alive
+const POLL_TIMEOUT_MS: i32 = 500;
+
+/// Starts monitoring video device nodes on a background thread
+pub fn watch() -> Result<Watcher> {
+    let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    let inotify = Inotify(fd);
+
+    // Udev usually adjusts the permissions of new nodes right after they were created, which is
+    // when we learn about them by means of IN_ATTRIB.
+    let path = CString::new(DEV_DIR).unwrap();
+    let mask = libc::IN_CREATE | libc::IN_DELETE | libc::IN_ATTRIB;
+    if unsafe { libc::inotify_add_watch(inotify.0, path.as_ptr(), mask) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    // Only changes are reported, so remember which devices are present already.
+    let known = context::enum_devices()
+        .into_iter()
+        .filter_map(|dev| describe(dev.index()).map(|desc| (dev.index(), desc.uri)))
+        .collect();
+
+    let (watcher, notifier) = Watcher::new();
+    thread::spawn(move || run(inotify, notifier, known));
+
+    Ok(watcher)
+}
+
+/// Owned inotify file descriptor
+struct Inotify(libc::c_int);
+
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+/// Devices are remembered by the URI they were announced with. Their udev symlinks are gone by the
+/// time they are removed, so the URI could not be determined anymore.
+fn run(inotify: Inotify, notifier: Notifier, mut known: HashMap<usize, String>) {
+    // large enough to hold many events at once, aligned for inotify_event
+    let mut buf = vec![0u64; 512];
+
+    while !notifier.is_closed() {
+        let mut pollfd = libc::pollfd {
+            fd: inotify.0,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut pollfd, 1, POLL_TIMEOUT_MS) } {
+            0 => continue,
+            n if n < 0 => {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let len = unsafe {
+            libc::read(
+                inotify.0,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len() * mem::size_of::<u64>(),
+            )
+        };
+        if len < 0 {
+            match io::Error::last_os_error().kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => continue,
+                _ => return,
+            }
+        }
+
+        let bytes = unsafe { std::slice::from_raw_parts(buf.as_ptr() as *const u8, len as usize) };
+        for (mask, name) in events(bytes) {
+            let index = match name
+                .strip_prefix("video")
+                .and_then(|index| index.parse::<usize>().ok())
+            {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let event = if mask & libc::IN_DELETE != 0 {
+                match known.remove(&index) {
+                    Some(uri) => Event::Removed(uri),
+                    None => continue,
+                }
+            } else {
+                if known.contains_key(&index) {
+                    continue;
+                }
+                // The node may not be accessible yet, in which case we try again on the next
+                // attribute change.
+                match describe(index) {
+                    Some(desc) => {
+                        known.insert(index, desc.uri.clone());
+                        Event::Added(desc)
+                    }
+                    None => continue,
+                }
+            };
+
+            if !notifier.notify(event) {
+                return;
+            }
+        }
+    }
+}
+
+/// Parses a buffer of inotify events into (mask, name) tuples
+fn events(mut bytes: &[u8]) -> impl Iterator<Item = (u32, String)> + '_ {
+    // struct inotify_event { int wd; uint32_t mask; uint32_t cookie; uint32_t len; char name[]; }
+    const HEADER_LEN: usize = 16;
+
+    std::iter::from_fn(move || {
+        if bytes.len() < HEADER_LEN {
+            return None;
+        }
+
+        let mask = u32::from_ne_bytes(bytes[4..8].try_into().unwrap());
+        let len = u32::from_ne_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        let end = (HEADER_LEN + len).min(bytes.len());
+
+        // the name is padded with NUL bytes
+        let name = &bytes[HEADER_LEN..end];
+        let name = name.split(|&b| b == 0).next().unwrap_or_default();
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        bytes = &bytes[end..];
+        Some((mask, name))
+    })
+}
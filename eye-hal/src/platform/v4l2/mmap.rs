@@ -0,0 +1,275 @@
+use std::ops::Deref;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{io, mem, ptr, slice};
+
+use v4l::buffer::{Metadata, Type as BufType};
+use v4l::device::Handle as DeviceHandle;
+use v4l::memory::Memory;
+use v4l::v4l2;
+use v4l::v4l_sys::{v4l2_buffer, v4l2_requestbuffers};
+
+/// Memory mapped buffer queue
+///
+/// Unlike the stream implementation of the v4l crate, this queue never blocks: dequeueing a buffer
+/// fails with [`io::ErrorKind::WouldBlock`] if none is ready. Callers wait for buffers by polling
+/// the device, which allows for timeouts and readiness based (async) I/O.
+pub struct Queue {
+    mapping: Arc<Mapping>,
+    active: bool,
+}
+
+impl Queue {
+    /// Allocates and maps the buffers
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Device handle
+    /// * `count` - Number of buffers to request, the driver may allocate more
+    pub fn new(handle: Arc<DeviceHandle>, count: u32) -> io::Result<Self> {
+        let mut mapping = Mapping {
+            handle,
+            bufs: Vec::new(),
+            lent: AtomicUsize::new(0),
+        };
+
+        let mut reqbufs = v4l2_requestbuffers {
+            count,
+            ..requestbuffers_desc()
+        };
+        mapping.ioctl(v4l2::vidioc::VIDIOC_REQBUFS, &mut reqbufs)?;
+
+        for index in 0..reqbufs.count {
+            let mut buf = v4l2_buffer {
+                index,
+                ..buffer_desc()
+            };
+            mapping.ioctl(v4l2::vidioc::VIDIOC_QUERYBUF, &mut buf)?;
+
+            let ptr = unsafe {
+                v4l2::mmap(
+                    ptr::null_mut(),
+                    buf.length as usize,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    mapping.handle.fd(),
+                    buf.m.offset as libc::off_t,
+                )?
+            };
+            mapping.bufs.push((ptr as *mut u8, buf.length as usize));
+        }
+
+        Ok(Queue {
+            mapping: Arc::new(mapping),
+            active: false,
+        })
+    }
+
+    #[cfg(feature = "async")]
+    /// Returns the raw device handle
+    pub fn handle(&self) -> &Arc<DeviceHandle> {
+        &self.mapping.handle
+    }
+
+    /// Returns the number of buffers
+    pub fn count(&self) -> usize {
+        self.mapping.bufs.len()
+    }
+
+    /// Returns the number of buffers currently lent out, see [`Queue::lend`]
+    pub fn lent(&self) -> usize {
+        self.mapping.lent.load(Ordering::Acquire)
+    }
+
+    /// Waits for the device to become ready
+    ///
+    /// Returns the events which occurred, which is empty if the timeout expired.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - Events to wait for, e.g. `POLLIN`
+    /// * `timeout` - Timeout in milliseconds, negative values wait forever
+    pub fn poll(&self, events: i16, timeout: i32) -> io::Result<i16> {
+        let mut fd = libc::pollfd {
+            fd: self.mapping.handle.fd(),
+            events,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut fd, 1, timeout) } {
+            -1 => Err(io::Error::last_os_error()),
+            _ => Ok(fd.revents),
+        }
+    }
+
+    /// Queues all buffers and starts streaming
+    pub fn start(&mut self) -> io::Result<()> {
+        if self.active {
+            return Ok(());
+        }
+
+        for index in 0..self.mapping.bufs.len() {
+            self.queue(index)?;
+        }
+
+        let mut typ = BufType::VideoCapture as u32;
+        self.mapping
+            .ioctl(v4l2::vidioc::VIDIOC_STREAMON, &mut typ)?;
+        self.active = true;
+        Ok(())
+    }
+
+    /// Stops streaming, all buffers are implicitly dequeued
+    pub fn stop(&mut self) -> io::Result<()> {
+        if !self.active {
+            return Ok(());
+        }
+
+        let mut typ = BufType::VideoCapture as u32;
+        self.mapping
+            .ioctl(v4l2::vidioc::VIDIOC_STREAMOFF, &mut typ)?;
+        self.active = false;
+        Ok(())
+    }
+
+    /// Hands a buffer back to the driver
+    pub fn queue(&mut self, index: usize) -> io::Result<()> {
+        self.mapping.queue(index)
+    }
+
+    /// Takes a filled buffer from the driver
+    ///
+    /// Returns the buffer index along with its metadata. The buffer must be queued again once its
+    /// contents are no longer needed.
+    pub fn dequeue(&mut self) -> io::Result<(usize, Metadata)> {
+        let mut buf = buffer_desc();
+        self.mapping.ioctl(v4l2::vidioc::VIDIOC_DQBUF, &mut buf)?;
+
+        let meta = Metadata {
+            bytesused: buf.bytesused,
+            flags: buf.flags.into(),
+            field: buf.field,
+            timestamp: buf.timestamp.into(),
+            sequence: buf.sequence,
+        };
+        Ok((buf.index as usize, meta))
+    }
+
+    /// Returns the contents of a buffer
+    pub fn buffer(&self, index: usize) -> &[u8] {
+        self.mapping.buffer(index)
+    }
+
+    /// Transfers the ownership of a dequeued buffer to a guard
+    ///
+    /// The buffer is queued again when the guard is dropped. Its mapping stays valid until then,
+    /// even if the queue itself is dropped earlier.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of a dequeued buffer
+    /// * `len` - Number of bytes used in the buffer
+    pub fn lend(&self, index: usize, len: usize) -> Buffer {
+        self.mapping.lent.fetch_add(1, Ordering::AcqRel);
+        Buffer {
+            mapping: Arc::clone(&self.mapping),
+            index,
+            len: len.min(self.mapping.bufs[index].1),
+        }
+    }
+}
+
+impl Drop for Queue {
+    fn drop(&mut self) {
+        // ignore the result, the device may be gone already
+        let _ = self.stop();
+    }
+}
+
+/// Dequeued buffer, queued again when dropped
+pub struct Buffer {
+    mapping: Arc<Mapping>,
+    index: usize,
+    len: usize,
+}
+
+impl Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.mapping.buffer(self.index)[..self.len]
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        // ignore the result, the stream may have been stopped in the meantime
+        let _ = self.mapping.queue(self.index);
+        self.mapping.lent.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Memory mapped driver buffers
+///
+/// The buffers are unmapped and freed once the queue as well as all dequeued buffers are gone.
+struct Mapping {
+    handle: Arc<DeviceHandle>,
+    bufs: Vec<(*mut u8, usize)>,
+    /// Number of buffers owned by guards
+    lent: AtomicUsize,
+}
+
+// The mapped memory is only ever written by the driver while a buffer is queued. Dequeued buffers
+// are owned by exactly one party at a time, which is enforced by the queue.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    fn buffer(&self, index: usize) -> &[u8] {
+        let (ptr, len) = self.bufs[index];
+        unsafe { slice::from_raw_parts(ptr, len) }
+    }
+
+    fn queue(&self, index: usize) -> io::Result<()> {
+        let mut buf = v4l2_buffer {
+            index: index as u32,
+            ..buffer_desc()
+        };
+        self.ioctl(v4l2::vidioc::VIDIOC_QBUF, &mut buf)
+    }
+
+    fn ioctl<T>(&self, request: v4l2::vidioc::_IOC_TYPE, arg: &mut T) -> io::Result<()> {
+        unsafe { v4l2::ioctl(self.handle.fd(), request, arg as *mut T as *mut c_void) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // ignore the results, the device may be gone already
+        for (ptr, len) in self.bufs.drain(..) {
+            unsafe {
+                let _ = v4l2::munmap(ptr as *mut c_void, len);
+            }
+        }
+
+        // free all buffers by requesting 0
+        let mut reqbufs = requestbuffers_desc();
+        let _ = self.ioctl(v4l2::vidioc::VIDIOC_REQBUFS, &mut reqbufs);
+    }
+}
+
+fn buffer_desc() -> v4l2_buffer {
+    v4l2_buffer {
+        type_: BufType::VideoCapture as u32,
+        memory: Memory::Mmap as u32,
+        ..unsafe { mem::zeroed() }
+    }
+}
+
+fn requestbuffers_desc() -> v4l2_requestbuffers {
+    v4l2_requestbuffers {
+        type_: BufType::VideoCapture as u32,
+        memory: Memory::Mmap as u32,
+        ..unsafe { mem::zeroed() }
+    }
+}
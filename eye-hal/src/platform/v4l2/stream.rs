@@ -1,67 +1,234 @@
-use v4l::buffer::Type as BufType;
-use v4l::io::mmap::Stream as MmapStream;
-use v4l::io::traits::{CaptureStream, Stream as _};
+use std::io;
+use std::time::{Duration, Instant};
 
-use crate::error::Result;
+use v4l::buffer::{Flags as BufFlags, Metadata};
+
+use crate::error::{Error, ErrorKind, Result};
 use crate::platform::v4l2::device::Handle as DeviceHandle;
+use crate::platform::v4l2::mmap::Queue;
+use crate::stream::{Flags, Format, Frame, FrameBuf};
 use crate::traits::Stream;
 
-pub struct Handle<'a> {
-    stream: MmapStream<'a>,
-    active: bool,
+#[cfg(feature = "async")]
+use std::{
+    os::unix::io::{AsRawFd, RawFd},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use futures_core::ready;
+#[cfg(feature = "async")]
+use tokio::io::unix::AsyncFd;
+
+pub struct Handle {
+    queue: Queue,
+    format: Format,
+    /// Buffer currently lent out to the caller
+    index: Option<usize>,
 }
 
-impl<'a> Handle<'a> {
-    pub fn new(dev: &DeviceHandle) -> Result<Self> {
-        let stream = MmapStream::new(dev.inner(), BufType::VideoCapture)?;
+impl Handle {
+    pub fn new(dev: &DeviceHandle, format: Format) -> Result<Self> {
+        let queue = Queue::new(dev.inner().handle(), 4)?;
         Ok(Handle {
-            stream,
-            active: false,
+            queue,
+            format,
+            index: None,
         })
     }
 
-    fn _start(&mut self) -> Result<()> {
-        if self.active {
-            return Ok(());
+    /// Waits for the next filled buffer
+    ///
+    /// The buffer returned by the previous call is handed back to the driver first.
+    fn dequeue(&mut self, timeout: Option<Duration>) -> Result<(usize, Metadata)> {
+        if let Some(index) = self.index.take() {
+            self.queue.queue(index)?;
+        }
+        self.queue.start()?;
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            match self.queue.dequeue() {
+                Ok((index, meta)) => {
+                    self.index = Some(index);
+                    return Ok((index, meta));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            // Buffers held by frame buffers are not queued, so the driver cannot fill them. With
+            // all of them lent out, waiting would never end.
+            if self.queue.lent() >= self.queue.count() {
+                return Err(Error::new(
+                    ErrorKind::Busy,
+                    "all buffers are held by frame buffers",
+                ));
+            }
+
+            // The device was opened in non-blocking mode, so we have to wait for a buffer to
+            // become available ourselves.
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    // round up so we do not spin on sub-millisecond remainders
+                    remaining.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32
+                }
+                None => -1,
+            };
+            let revents = self.queue.poll(libc::POLLIN, timeout)?;
+            if revents & libc::POLLIN != 0 {
+                continue;
+            } else if revents & libc::POLLHUP != 0 {
+                return Err(Error::new(ErrorKind::Disconnected, "device hung up"));
+            } else if revents & (libc::POLLERR | libc::POLLNVAL) != 0 {
+                return Err(Error::new(ErrorKind::Io, "device reported an error"));
+            } else {
+                return Err(Error::new(ErrorKind::Timeout, "no frame available"));
+            }
         }
+    }
+
+    fn frame(&self, index: usize, meta: &Metadata) -> Frame<'_> {
+        // For compressed formats, the buffer length will not actually describe the number
+        // of bytes in a frame. Instead, we have to explicitly query about the amount of
+        // used bytes.
+        let buf = self.queue.buffer(index);
+        let len = (meta.bytesused as usize).min(buf.len());
+        Frame::new(&buf[..len])
+            .timestamp(Duration::from(meta.timestamp))
+            .sequence(meta.sequence as u64)
+            .flags(flags(meta))
+    }
+
+    /// Captures a frame without copying it
+    ///
+    /// The returned frame buffer holds on to the driver buffer and hands it back once it is
+    /// dropped. While all driver buffers are held, an error of kind [`ErrorKind::Busy`] is
+    /// returned instead of waiting for a frame.
+    pub fn next_buf(&mut self, timeout: Option<Duration>) -> Result<FrameBuf> {
+        let (index, meta) = self.dequeue(timeout)?;
+        // the buffer is owned by the frame buffer from now on
+        self.index = None;
+
+        let buf = self.queue.lend(index, meta.bytesused as usize);
+        Ok(FrameBuf::native(buf, &self.frame(index, &meta)))
+    }
+
+    #[cfg(feature = "async")]
+    /// Converts the stream into an asynchronous one
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn into_async(mut self) -> Result<AsyncHandle> {
+        if let Some(index) = self.index.take() {
+            self.queue.queue(index)?;
+        }
+
+        AsyncHandle::new(self.queue)
+    }
+}
+
+impl<'a> Stream<'a> for Handle {
+    type Item = Result<Frame<'a>>;
+
+    fn format(&self) -> Format {
+        self.format.clone()
+    }
 
-        self.stream.start()?;
-        self.active = true;
-        Ok(())
+    fn next(&'a mut self) -> Option<Self::Item> {
+        match self.dequeue(None) {
+            Ok((index, meta)) => Some(Ok(self.frame(index, &meta))),
+            Err(e) => Some(Err(e)),
+        }
     }
 
-    fn stop(&mut self) -> Result<()> {
-        if !self.active {
-            return Ok(());
+    fn next_timeout(&'a mut self, timeout: Duration) -> Option<Self::Item> {
+        match self.dequeue(Some(timeout)) {
+            Ok((index, meta)) => Some(Ok(self.frame(index, &meta))),
+            Err(e) => Some(Err(e)),
         }
+    }
+}
 
-        self.stream.stop()?;
-        self.active = false;
-        Ok(())
+fn flags(meta: &Metadata) -> Flags {
+    let mut flags = Flags::NONE;
+    if meta.flags.contains(BufFlags::ERROR) {
+        flags.insert(Flags::ERROR);
     }
+    if meta.flags.contains(BufFlags::KEYFRAME) {
+        flags.insert(Flags::KEYFRAME);
+    }
+    flags
 }
 
-impl<'a> Drop for Handle<'a> {
-    fn drop(&mut self) {
-        if self.active {
-            // ignore the result
-            let _ = self.stop();
+#[cfg(feature = "async")]
+/// Device file descriptor registered with the tokio reactor
+///
+/// Holding on to the handle keeps the file descriptor open for as long as it is registered.
+struct Fd(Arc<v4l::device::Handle>);
+
+#[cfg(feature = "async")]
+impl AsRawFd for Fd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.fd()
+    }
+}
+
+#[cfg(feature = "async")]
+/// Asynchronous stream which waits for frames by polling the device file descriptor
+///
+/// Frames are copied out of the mapped buffers, so the buffers can be handed back to the driver
+/// right away.
+pub struct AsyncHandle {
+    fd: AsyncFd<Fd>,
+    queue: Queue,
+}
+
+#[cfg(feature = "async")]
+impl AsyncHandle {
+    pub fn new(mut queue: Queue) -> Result<Self> {
+        // SAFETY: the file descriptor is owned by the handle and is not closed before the
+        // registration is dropped.
+        let fd = unsafe {
+            AsyncFd::register_with_interest(
+                Fd(queue.handle().clone()),
+                tokio::io::Interest::READABLE,
+            )
         }
+        .map_err(io::Error::from)?;
+        queue.start()?;
+
+        Ok(AsyncHandle { fd, queue })
     }
 }
 
-impl<'a, 'b> Stream<'b> for Handle<'a> {
-    type Item = Result<&'b [u8]>;
+#[cfg(feature = "async")]
+impl futures_core::Stream for AsyncHandle {
+    type Item = Result<Frame<'static>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let mut guard = ready!(this.fd.poll_read_ready(cx))?;
+
+            match this.queue.dequeue() {
+                Ok((index, meta)) => {
+                    let buf = this.queue.buffer(index);
+                    let buf = &buf[..(meta.bytesused as usize).min(buf.len())];
+                    let frame = Frame::new(buf.to_vec())
+                        .timestamp(Duration::from(meta.timestamp))
+                        .sequence(meta.sequence as u64)
+                        .flags(flags(&meta));
 
-    fn next(&'b mut self) -> Option<Self::Item> {
-        match self.stream.next() {
-            Ok((buf, meta)) => {
-                // For compressed formats, the buffer length will not actually describe the number
-                // of bytes in a frame. Instead, we have to explicitly query about the amount of
-                // used bytes.
-                Some(Ok(&buf[0..meta.bytesused as usize]))
+                    this.queue.queue(index)?;
+                    return Poll::Ready(Some(Ok(frame)));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => guard.clear_ready(),
+                Err(e) => return Poll::Ready(Some(Err(e.into()))),
             }
-            Err(e) => Some(Err(e.into())),
         }
     }
 }
@@ -0,0 +1,100 @@
+//! Persistent device URIs
+//!
+//! Device node numbers (/dev/videoN) are handed out in the order in which devices are probed, so
+//! they may change across reboots and replugs. The v4l2 backend therefore prefers the symlinks
+//! maintained by udev, which are named after properties of the device:
+//!
+//! * `v4l:///dev/v4l/by-id/usb-Vendor_Product_Serial-video-index0` - physical device
+//! * `v4l:///dev/v4l/by-path/pci-0000:00:14.0-usb-0:1:1.0-video-index0` - port it is plugged into
+//!
+//! Additionally, USB devices can be addressed by their serial number through URIs of the form
+//! `v4l://serial/XYZ`, which do not depend on udev at all.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use v4l::context;
+
+use crate::platform::v4l2::context::{describe, usb};
+
+/// Directory containing the symlinks named after the physical devices
+const BY_ID_DIR: &str = "/dev/v4l/by-id";
+
+/// Directory containing the symlinks named after the ports the devices are plugged into
+const BY_PATH_DIR: &str = "/dev/v4l/by-path";
+
+/// Returns the most persistent URI of a device node
+///
+/// Links by ID are only considered if the device has a serial number. Without one, identical
+/// devices share the same ID and udev points the link at whichever device showed up last.
+///
+/// # Arguments
+///
+/// * `index` - Index of the device node
+pub fn stable(index: usize) -> String {
+    let serial = usb(index).and_then(|usb| usb.serial);
+
+    let link = serial
+        .and_then(|_| link(BY_ID_DIR, index))
+        .or_else(|| link(BY_PATH_DIR, index));
+
+    match link {
+        Some(link) => format!("v4l://{}", link.display()),
+        None => format!("v4l:///dev/video{}", index),
+    }
+}
+
+/// Returns the path of the device node identified by a URI
+///
+/// # Arguments
+///
+/// * `uri` - Device URI, either containing a path or a serial number
+pub fn resolve(uri: &str) -> io::Result<PathBuf> {
+    let path = match uri.strip_prefix("v4l://") {
+        Some(path) => path,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid URI")),
+    };
+
+    if let Some(serial) = path.strip_prefix("serial/") {
+        return by_serial(serial).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no device with serial number {}", serial),
+            )
+        });
+    }
+
+    Ok(PathBuf::from(path))
+}
+
+/// Returns the first capture node of the USB device with the given serial number
+fn by_serial(serial: &str) -> Option<PathBuf> {
+    let mut indices: Vec<usize> = context::enum_devices()
+        .into_iter()
+        .map(|dev| dev.index())
+        .collect();
+    // A device may expose several nodes, e.g. one for capturing and one for metadata.
+    indices.sort_unstable();
+
+    indices
+        .into_iter()
+        .filter(|index| usb(*index).and_then(|usb| usb.serial).as_deref() == Some(serial))
+        .find(|index| describe(*index).is_some())
+        .map(|index| PathBuf::from(format!("/dev/video{}", index)))
+}
+
+/// Returns the first symlink in a directory which points to a device node
+fn link(dir: &str, index: usize) -> Option<PathBuf> {
+    let node = Path::new("/dev").join(format!("video{}", index));
+
+    let mut links: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| fs::canonicalize(path).ok().as_deref() == Some(node.as_path()))
+        .collect();
+    // read_dir does not guarantee any order
+    links.sort();
+
+    links.into_iter().next()
+}
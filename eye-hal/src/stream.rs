@@ -1,8 +1,15 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
 use std::time;
 
-use crate::format::PixelFormat;
+use bitflags::bitflags;
 
-#[derive(Clone, Debug)]
+use crate::format::{ImageFormat, PixelFormat, Plane};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 /// Image stream description
 pub struct Descriptor {
     /// Width in pixels
@@ -11,6 +18,713 @@ pub struct Descriptor {
     pub height: u32,
     /// PixelFormat
     pub pixfmt: PixelFormat,
-    /// Frame timing as duration
-    pub interval: time::Duration,
+    /// Frame timing
+    pub interval: Interval,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Frame interval in seconds, as an exact fraction
+///
+/// Frame rates such as 29.97 fps (30000/1001) cannot be represented exactly by floating point
+/// numbers or durations, so backends pass intervals as fractions.
+///
+/// Intervals compare by their value, so 1/30 equals 2/60.
+///
+/// An interval of zero seconds means that the frame timing is unknown, e.g. because the device
+/// does not report it. Intervals with a zero numerator or denominator are treated as such, so
+/// 0/1, 0/30 and 1/0 all compare equal and less than any other interval.
+pub struct Interval {
+    /// Numerator (seconds)
+    pub numerator: u32,
+    /// Denominator
+    pub denominator: u32,
+}
+
+impl Interval {
+    /// Returns the interval `numerator / denominator` seconds
+    ///
+    /// If either term is zero, the interval is normalized to 0/1, i.e. zero seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `numerator` - Numerator
+    /// * `denominator` - Denominator
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eye_hal::stream::Interval;
+    /// assert_eq!(Interval::new(1, 0), Interval::default());
+    /// assert_eq!(Interval::new(1, 0).denominator, 1);
+    /// ```
+    pub fn new(numerator: u32, denominator: u32) -> Self {
+        if numerator == 0 || denominator == 0 {
+            return Interval {
+                numerator: 0,
+                denominator: 1,
+            };
+        }
+
+        Interval {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Returns the interval of a frame rate
+    ///
+    /// # Arguments
+    ///
+    /// * `fps` - Frames per second
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eye_hal::stream::Interval;
+    /// assert_eq!(Interval::from_fps(30), Interval::new(1, 30));
+    /// ```
+    pub fn from_fps(fps: u32) -> Self {
+        Interval::new(1, fps)
+    }
+
+    /// Returns true if the interval is zero seconds, i.e. the frame timing is unknown
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0 || self.denominator == 0
+    }
+
+    /// Returns the frame rate in frames per second, or zero if the interval is zero seconds
+    pub fn fps(&self) -> f64 {
+        if self.is_zero() {
+            return 0.0;
+        }
+
+        self.denominator as f64 / self.numerator as f64
+    }
+
+    /// Returns the interval as duration, rounded to nanoseconds
+    pub fn as_duration(&self) -> time::Duration {
+        self.elapsed(1)
+    }
+
+    /// Returns the time it takes to capture a number of frames, rounded to nanoseconds
+    ///
+    /// Unlike multiplying the rounded duration, this does not accumulate rounding errors.
+    pub(crate) fn elapsed(&self, frames: u64) -> time::Duration {
+        if self.is_zero() {
+            return time::Duration::ZERO;
+        }
+
+        let nanos = frames as u128 * self.numerator as u128 * 1_000_000_000;
+        time::Duration::from_nanos((nanos / self.denominator as u128) as u64)
+    }
+}
+
+impl Default for Interval {
+    /// Returns an interval of zero seconds
+    fn default() -> Self {
+        Interval::new(0, 1)
+    }
+}
+
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Interval {}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The fields are public, so the terms are not necessarily normalized.
+        let lhs = Interval::new(self.numerator, self.denominator);
+        let rhs = Interval::new(other.numerator, other.denominator);
+        let lhs_cross = lhs.numerator as u64 * rhs.denominator as u64;
+        let rhs_cross = rhs.numerator as u64 * lhs.denominator as u64;
+        lhs_cross.cmp(&rhs_cross)
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+impl From<Interval> for time::Duration {
+    fn from(interval: Interval) -> Self {
+        interval.as_duration()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Values between a minimum and a maximum, spaced by a step
+///
+/// A step of zero means that there is only a single value, the minimum.
+pub struct Bounds<T> {
+    /// Smallest value
+    pub min: T,
+    /// Largest value
+    pub max: T,
+    /// Distance between two adjacent values
+    pub step: T,
+}
+
+impl<T: Copy + Default> Bounds<T> {
+    /// Returns bounds containing a single value
+    pub fn fixed(value: T) -> Self {
+        Bounds {
+            min: value,
+            max: value,
+            step: T::default(),
+        }
+    }
+}
+
+impl Bounds<u32> {
+    /// Returns true if the value is one of the values described by the bounds
+    pub fn contains(&self, value: u32) -> bool {
+        if value < self.min || value > self.max {
+            return false;
+        }
+
+        match self.step {
+            0 => value == self.min,
+            step => (value - self.min).is_multiple_of(step),
+        }
+    }
+}
+
+impl Bounds<Interval> {
+    /// Returns true if the value lies between the minimum and the maximum
+    ///
+    /// The step is not taken into account: drivers round intervals to the nearest one they
+    /// support anyways.
+    pub fn contains(&self, value: Interval) -> bool {
+        value >= self.min && value <= self.max
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Range of streams supported by a device
+///
+/// Some devices, such as capture cards and industrial cameras, support arbitrary frame sizes and
+/// intervals within certain bounds instead of a fixed set. Any stream contained in a range can be
+/// started.
+pub struct Range {
+    /// PixelFormat
+    pub pixfmt: PixelFormat,
+    /// Width in pixels
+    pub width: Bounds<u32>,
+    /// Height in pixels
+    pub height: Bounds<u32>,
+    /// Frame timing
+    pub interval: Bounds<Interval>,
+}
+
+impl Range {
+    /// Returns true if the stream is contained in this range
+    ///
+    /// # Arguments
+    ///
+    /// * `desc` - Stream descriptor
+    pub fn contains(&self, desc: &Descriptor) -> bool {
+        self.pixfmt == desc.pixfmt
+            && self.width.contains(desc.width)
+            && self.height.contains(desc.height)
+            && self.interval.contains(desc.interval)
+    }
+
+    /// Returns a representative set of streams contained in this range
+    ///
+    /// Listing all streams of a range is not feasible, so this picks common frame sizes and rates
+    /// in addition to the bounds themselves.
+    pub fn descriptors(&self) -> Vec<Descriptor> {
+        const SIZES: [(u32, u32); 10] = [
+            (160, 120),
+            (320, 240),
+            (640, 480),
+            (800, 600),
+            (1024, 768),
+            (1280, 720),
+            (1280, 960),
+            (1920, 1080),
+            (2560, 1440),
+            (3840, 2160),
+        ];
+        const RATES: [u32; 11] = [5, 10, 15, 20, 24, 25, 30, 50, 60, 90, 120];
+
+        let mut sizes: Vec<(u32, u32)> = std::iter::once((self.width.min, self.height.min))
+            .chain(SIZES.iter().copied())
+            .chain(std::iter::once((self.width.max, self.height.max)))
+            .filter(|(width, height)| self.width.contains(*width) && self.height.contains(*height))
+            .collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+
+        // low frame rates first, so the cheapest streams come first like with the sizes
+        let mut intervals: Vec<Interval> = RATES
+            .iter()
+            .map(|fps| Interval::from_fps(*fps))
+            .filter(|interval| self.interval.contains(*interval))
+            .chain(std::iter::once(self.interval.min))
+            .chain(std::iter::once(self.interval.max))
+            .collect();
+        intervals.sort_unstable_by(|a, b| b.cmp(a));
+        intervals.dedup();
+
+        sizes
+            .into_iter()
+            .flat_map(|(width, height)| {
+                intervals.iter().map(move |interval| Descriptor {
+                    width,
+                    height,
+                    pixfmt: self.pixfmt.clone(),
+                    interval: *interval,
+                })
+            })
+            .collect()
+    }
+}
+
+impl From<&Descriptor> for Range {
+    fn from(desc: &Descriptor) -> Self {
+        Range {
+            pixfmt: desc.pixfmt.clone(),
+            width: Bounds::fixed(desc.width),
+            height: Bounds::fixed(desc.height),
+            interval: Bounds::fixed(desc.interval),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Format of a running stream
+///
+/// Drivers are free to adjust the requested stream, e.g. by rounding the frame interval, so the
+/// format describes what the stream actually produces.
+pub struct Format {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// PixelFormat
+    pub pixfmt: PixelFormat,
+    /// Length of a pixel row in bytes (of the first plane for planar formats), zero if unknown
+    /// (e.g. for compressed formats)
+    pub stride: usize,
+    /// Maximum size of a frame in bytes, zero if unknown
+    pub size: usize,
+    /// Memory layout of the planes, empty for compressed and unknown formats
+    pub planes: Vec<Plane>,
+    /// Frame timing
+    pub interval: Interval,
+}
+
+impl Format {
+    /// Returns the format of a stream producing tightly packed frames
+    ///
+    /// # Arguments
+    ///
+    /// * `desc` - Stream descriptor
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eye_hal::format::PixelFormat;
+    /// use eye_hal::stream::{Descriptor, Format, Interval};
+    ///
+    /// let format = Format::packed(&Descriptor {
+    ///     width: 640,
+    ///     height: 480,
+    ///     pixfmt: PixelFormat::Rgb(24),
+    ///     interval: Interval::from_fps(30),
+    /// });
+    /// assert_eq!(format.stride, 640 * 3);
+    /// assert_eq!(format.size, 640 * 480 * 3);
+    /// ```
+    pub fn packed(desc: &Descriptor) -> Self {
+        let image = ImageFormat::new(desc.width, desc.height, desc.pixfmt.clone());
+
+        Format {
+            width: desc.width,
+            height: desc.height,
+            pixfmt: desc.pixfmt.clone(),
+            stride: image.planes.first().map_or(0, |plane| plane.stride),
+            size: image.size().unwrap_or(0),
+            planes: image.planes,
+            interval: desc.interval,
+        }
+    }
+
+    /// Returns the layout of the frames
+    pub fn image(&self) -> ImageFormat {
+        ImageFormat {
+            width: self.width,
+            height: self.height,
+            pixfmt: self.pixfmt.clone(),
+            planes: self.planes.clone(),
+        }
+    }
+
+    /// Returns the descriptor of the stream
+    pub fn descriptor(&self) -> Descriptor {
+        Descriptor {
+            width: self.width,
+            height: self.height,
+            pixfmt: self.pixfmt.clone(),
+            interval: self.interval,
+        }
+    }
+}
+
+bitflags! {
+    /// Frame flags
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct Flags: u32 {
+        /// No flags are set
+        const NONE                  = 0x000;
+        /// Frame data is corrupted, but the frame was delivered anyways
+        const ERROR                 = 0x001;
+        /// Frame is a keyframe (e.g. an I-frame of a compressed stream)
+        const KEYFRAME              = 0x002;
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Image frame captured by a stream
+///
+/// Frames borrow their data from the stream whenever possible. Use [`Frame::into_owned`] to
+/// detach a frame from its stream.
+pub struct Frame<'a> {
+    /// Image data
+    pub data: Cow<'a, [u8]>,
+    /// Capture time as reported by the backend, relative to a backend specific epoch
+    pub timestamp: Option<time::Duration>,
+    /// Frame sequence number, counting the frames of the stream
+    pub sequence: u64,
+    /// Number of bytes occupied by the image data
+    pub bytesused: usize,
+    /// Frame flags
+    pub flags: Flags,
+}
+
+impl<'a> Frame<'a> {
+    /// Returns a frame without any metadata
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Image data
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eye_hal::stream::Frame;
+    /// let frame = Frame::new(&[0u8; 4][..]).sequence(1);
+    /// ```
+    pub fn new<D: Into<Cow<'a, [u8]>>>(data: D) -> Self {
+        let data = data.into();
+        let bytesused = data.len();
+
+        Frame {
+            data,
+            timestamp: None,
+            sequence: 0,
+            bytesused,
+            flags: Flags::NONE,
+        }
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - Capture time
+    pub fn timestamp(mut self, timestamp: time::Duration) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - Frame sequence number
+    pub fn sequence(mut self, sequence: u64) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - Frame flags
+    pub fn flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Returns a frame with the same metadata, but different image data
+    ///
+    /// This is useful for frame conversions which should preserve the metadata of the source.
+    pub fn with_data<'b, D: Into<Cow<'b, [u8]>>>(&self, data: D) -> Frame<'b> {
+        let data = data.into();
+        let bytesused = data.len();
+
+        Frame {
+            data,
+            timestamp: self.timestamp,
+            sequence: self.sequence,
+            bytesused,
+            flags: self.flags,
+        }
+    }
+
+    /// Copies the image data if necessary so the frame no longer borrows from its stream
+    pub fn into_owned(self) -> Frame<'static> {
+        Frame {
+            data: Cow::Owned(self.data.into_owned()),
+            timestamp: self.timestamp,
+            sequence: self.sequence,
+            bytesused: self.bytesused,
+            flags: self.flags,
+        }
+    }
+}
+
+impl<'a> Deref for Frame<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl<'a> AsRef<[u8]> for Frame<'a> {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Storage of an owned frame
+enum Data {
+    /// Heap allocated buffer, handed back to the pool (if any) when dropped
+    Vec(Vec<u8>, Option<Pool>),
+    /// Native buffer of a backend, e.g. a memory mapped driver buffer
+    Native(Box<dyn Deref<Target = [u8]> + Send + Sync>),
+}
+
+/// Owned image frame
+///
+/// Unlike [`Frame`], a frame buffer does not borrow from its stream, so it can be kept around and
+/// moved to other threads. Depending on the backend, it either holds on to a native buffer which
+/// is handed back to the driver once the frame buffer is dropped, or to a copy of the image data
+/// allocated from a [`Pool`].
+///
+/// Native buffers are a limited resource: a stream cannot capture new frames while all of its
+/// buffers are held by frame buffers. Capturing fails with an error of kind [`ErrorKind::Busy`]
+/// in that case, so drop frame buffers as soon as they are no longer needed.
+///
+/// [`ErrorKind::Busy`]: crate::error::ErrorKind::Busy
+pub struct FrameBuf {
+    data: Data,
+    /// Capture time as reported by the backend, relative to a backend specific epoch
+    pub timestamp: Option<time::Duration>,
+    /// Frame sequence number, counting the frames of the stream
+    pub sequence: u64,
+    /// Number of bytes occupied by the image data
+    pub bytesused: usize,
+    /// Frame flags
+    pub flags: Flags,
+}
+
+impl FrameBuf {
+    /// Returns a frame buffer backed by a native buffer of a backend
+    pub(crate) fn native<B>(buf: B, frame: &Frame) -> Self
+    where
+        B: Deref<Target = [u8]> + Send + Sync + 'static,
+    {
+        FrameBuf {
+            data: Data::Native(Box::new(buf)),
+            timestamp: frame.timestamp,
+            sequence: frame.sequence,
+            bytesused: frame.bytesused,
+            flags: frame.flags,
+        }
+    }
+
+    /// Returns a frame borrowing the image data of this buffer
+    ///
+    /// This is useful for passing the frame to APIs which expect a [`Frame`], such as codecs.
+    pub fn as_frame(&self) -> Frame<'_> {
+        Frame {
+            data: Cow::Borrowed(&self[..]),
+            timestamp: self.timestamp,
+            sequence: self.sequence,
+            bytesused: self.bytesused,
+            flags: self.flags,
+        }
+    }
+}
+
+impl<'a> From<Frame<'a>> for FrameBuf {
+    fn from(frame: Frame<'a>) -> Self {
+        FrameBuf {
+            data: Data::Vec(frame.data.into_owned(), None),
+            timestamp: frame.timestamp,
+            sequence: frame.sequence,
+            bytesused: frame.bytesused,
+            flags: frame.flags,
+        }
+    }
+}
+
+impl Deref for FrameBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.data {
+            Data::Vec(buf, _) => buf,
+            Data::Native(buf) => buf,
+        }
+    }
+}
+
+impl AsRef<[u8]> for FrameBuf {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for FrameBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FrameBuf")
+            .field("len", &self.len())
+            .field("timestamp", &self.timestamp)
+            .field("sequence", &self.sequence)
+            .field("bytesused", &self.bytesused)
+            .field("flags", &self.flags)
+            .finish()
+    }
+}
+
+impl Drop for FrameBuf {
+    fn drop(&mut self) {
+        if let Data::Vec(buf, Some(pool)) = &mut self.data {
+            pool.put(std::mem::take(buf));
+        }
+    }
+}
+
+#[derive(Clone)]
+/// Recycles the allocations of frame buffers
+///
+/// Frame buffers copied from a pool return their allocation to it when they are dropped, so a
+/// stream running at a constant frame size quickly stops allocating.
+pub struct Pool {
+    inner: Arc<Mutex<Vec<Vec<u8>>>>,
+    capacity: usize,
+}
+
+impl Pool {
+    /// Returns an empty pool
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of idle allocations to keep around
+    pub fn new(capacity: usize) -> Self {
+        Pool {
+            inner: Arc::new(Mutex::new(Vec::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Copies a frame into a pooled frame buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Frame to copy
+    pub fn copy(&self, frame: &Frame) -> FrameBuf {
+        let mut buf = self.take();
+        buf.clear();
+        buf.extend_from_slice(frame);
+
+        FrameBuf {
+            data: Data::Vec(buf, Some(self.clone())),
+            timestamp: frame.timestamp,
+            sequence: frame.sequence,
+            bytesused: frame.bytesused,
+            flags: frame.flags,
+        }
+    }
+
+    fn take(&self) -> Vec<u8> {
+        let mut bufs = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        bufs.pop().unwrap_or_default()
+    }
+
+    fn put(&self, buf: Vec<u8>) {
+        let mut bufs = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if bufs.len() < self.capacity {
+            bufs.push(buf);
+        }
+    }
+}
+
+impl Default for Pool {
+    /// Returns a pool suitable for a single stream
+    fn default() -> Self {
+        Pool::new(4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_order() {
+        assert_eq!(Interval::new(1, 30), Interval::new(2, 60));
+        assert_eq!(Interval::new(1001, 30000), Interval::new(2002, 60000));
+        assert!(Interval::new(1, 60) < Interval::new(1, 30));
+        assert!(Interval::new(1001, 30000) > Interval::new(1, 30));
+        assert!(Interval::new(1, 1) > Interval::new(u32::MAX - 1, u32::MAX));
+    }
+
+    #[test]
+    fn interval_zero() {
+        assert_eq!(Interval::new(0, 30), Interval::default());
+        assert_eq!(Interval::new(1, 0), Interval::default());
+        assert!(Interval::default() < Interval::new(1, u32::MAX));
+        assert_ne!(Interval::new(0, 0), Interval::new(1, 30));
+        assert_eq!(Interval::default().fps(), 0.0);
+        assert_eq!(Interval::default().as_duration(), time::Duration::ZERO);
+
+        // terms bypassing the constructor compare the same
+        let raw = Interval {
+            numerator: 0,
+            denominator: 0,
+        };
+        assert_eq!(raw, Interval::default());
+        assert!(raw < Interval::new(1, 30));
+        assert_eq!(raw.fps(), 0.0);
+    }
+
+    #[test]
+    fn interval_elapsed() {
+        let interval = Interval::new(1001, 30000);
+        assert_eq!(interval.fps(), 30000.0 / 1001.0);
+        assert_eq!(interval.elapsed(30000), time::Duration::from_secs(1001));
+        assert_eq!(
+            interval.as_duration(),
+            time::Duration::from_nanos(33_366_666)
+        );
+    }
 }
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use crate::control;
 use crate::device;
-use crate::error::Result;
+use crate::error::{Error, ErrorKind, Result};
 use crate::stream;
 
 /// Platform context abstraction
@@ -13,6 +15,16 @@ pub trait Context<'a> {
 
     /// Opens a device handle
     fn open_device(&self, uri: &str) -> Result<Self::Device>;
+
+    /// Starts monitoring devices as they are plugged and unplugged
+    ///
+    /// Only changes are reported, use [`Context::devices`] to get the initial set of devices.
+    fn watch(&self) -> Result<device::Watcher> {
+        Err(Error::new(
+            ErrorKind::NotSupported,
+            "hotplug monitoring is not supported",
+        ))
+    }
 }
 
 /// Platform device abstraction
@@ -23,17 +35,73 @@ pub trait Device<'a> {
     /// Returns the supported streams
     fn streams(&self) -> Result<Vec<stream::Descriptor>>;
 
+    /// Returns the supported ranges of streams
+    ///
+    /// Devices which support arbitrary frame sizes or intervals within certain bounds report
+    /// those as ranges, whereas [`Device::streams`] only lists some representative streams.
+    fn stream_ranges(&self) -> Result<Vec<stream::Range>> {
+        Ok(self.streams()?.iter().map(stream::Range::from).collect())
+    }
+
     /// Returns a stream which produces images
     fn start_stream(&self, desc: &stream::Descriptor) -> Result<Self::Stream>;
 
     /// Returns the supported controls
     fn controls(&self) -> Result<Vec<control::Descriptor>>;
 
+    /// Returns the IDs of the supported controls along with their well-known function, if any
+    ///
+    /// Unlike [`Device::controls`], this does not read the current values, which takes a round
+    /// trip to the device per control on most platforms.
+    fn control_ids(&self) -> Result<Vec<(u32, Option<control::Kind>)>> {
+        Ok(self
+            .controls()?
+            .into_iter()
+            .map(|desc| (desc.id, desc.kind))
+            .collect())
+    }
+
     /// Returns the current control value for an ID
     fn control(&self, id: u32) -> Result<control::State>;
 
     /// Sets the control value, returns error for incompatible value types
     fn set_control(&mut self, id: u32, val: &control::State) -> Result<()>;
+
+    /// Subscribes to changes of the controls
+    ///
+    /// Only changes are reported, use [`Device::controls`] to get the initial values.
+    fn subscribe(&self) -> Result<control::Subscription<'a>> {
+        Err(Error::new(
+            ErrorKind::NotSupported,
+            "control events are not supported",
+        ))
+    }
+
+    /// Returns the current value of a well-known control
+    fn control_by_kind(&self, kind: control::Kind) -> Result<control::State> {
+        let id = control_id(self, kind)?;
+        self.control(id)
+    }
+
+    /// Sets the value of a well-known control, returns error for incompatible value types
+    fn set_control_by_kind(&mut self, kind: control::Kind, val: &control::State) -> Result<()> {
+        let id = control_id(self, kind)?;
+        self.set_control(id, val)
+    }
+}
+
+/// Returns the ID of the first control of a kind
+fn control_id<'a, D: Device<'a> + ?Sized>(dev: &D, kind: control::Kind) -> Result<u32> {
+    dev.control_ids()?
+        .into_iter()
+        .find(|(_, other)| *other == Some(kind))
+        .map(|(id, _)| id)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("no control of kind {:?}", kind),
+            )
+        })
 }
 
 /// Stream abstraction
@@ -44,6 +112,29 @@ pub trait Stream<'a> {
     /// Type of the stream elements
     type Item;
 
+    /// Returns the format of the items produced by the stream
+    ///
+    /// Drivers may adjust the stream, so this is not necessarily the descriptor the stream was
+    /// started with.
+    fn format(&self) -> stream::Format;
+
     /// Advances the stream and returns the next item
     fn next(&'a mut self) -> Option<Self::Item>;
+
+    /// Advances the stream and returns the next item, waiting no longer than `timeout` for it
+    ///
+    /// If no item becomes available in time, an error of kind [`ErrorKind::Timeout`] is returned
+    /// and the stream remains usable.
+    ///
+    /// [`ErrorKind::Timeout`]: crate::error::ErrorKind::Timeout
+    fn next_timeout(&'a mut self, timeout: Duration) -> Option<Self::Item>;
+
+    /// Advances the stream and returns the next item without blocking
+    ///
+    /// If no item is available right away, an error of kind [`ErrorKind::Timeout`] is returned.
+    ///
+    /// [`ErrorKind::Timeout`]: crate::error::ErrorKind::Timeout
+    fn try_next(&'a mut self) -> Option<Self::Item> {
+        self.next_timeout(Duration::ZERO)
+    }
 }
@@ -1,6 +1,7 @@
 use std::{sync::mpsc, thread, time::Instant};
 
 use eye::colorconvert::Device;
+use eye::select::Constraints;
 use eye_hal::{
     format::PixelFormat,
     traits::{Context as _, Device as _, Stream as _},
@@ -10,14 +11,7 @@ use glium::{glutin, implement_vertex, index::PrimitiveType, program, uniform, Su
 
 fn main() -> Result<()> {
     // Create a context
-    let ctx = if let Some(ctx) = PlatformContext::all().next() {
-        ctx
-    } else {
-        return Err(Error::new(
-            ErrorKind::Other,
-            "No platform context available",
-        ));
-    };
+    let ctx = PlatformContext::default();
 
     // Create a list of valid capture devices in the system.
     let dev_descrs = ctx.devices()?;
@@ -28,31 +22,24 @@ fn main() -> Result<()> {
     // Print the supported formats for each device.
     let dev = ctx.open_device(&dev_descrs[index].uri)?;
     let dev = Device::new(dev)?;
-    let stream_descr = dev
-        .streams()?
-        .into_iter()
-        .reduce(|s1, s2| {
-            // Choose RGB with 8 bit depth
-            if s1.pixfmt == PixelFormat::Rgb(24) && s2.pixfmt != PixelFormat::Rgb(24) {
-                return s1;
-            }
-
-            // Strive for HD (1280 x 720)
-            let distance = |width: u32, height: u32| {
-                f32::sqrt(((1280 - width as i32).pow(2) + (720 - height as i32).pow(2)) as f32)
-            };
 
-            if distance(s1.width, s1.height) < distance(s2.width, s2.height) {
-                s1
-            } else {
-                s2
+    // Choose RGB with 8 bit depth and strive for HD (1280 x 720)
+    let constraints = Constraints::new()
+        .resolution(1280, 720)
+        .require_pixfmt(PixelFormat::Rgb(24));
+    let selection = dev.select_stream(&constraints)?;
+    let stream_descr = match selection.best() {
+        Some(desc) => desc.clone(),
+        None => {
+            for rejection in &selection.rejected {
+                println!("Rejected stream: {}", rejection);
             }
-        })
-        .unwrap();
-
-    if stream_descr.pixfmt != PixelFormat::Rgb(24) {
-        return Err(Error::new(ErrorKind::Other, "No RGB3 streams available"));
-    }
+            return Err(Error::new(
+                ErrorKind::UnsupportedFormat,
+                "No RGB3 streams available",
+            ));
+        }
+    };
 
     println!("Selected stream:\n{:?}", stream_descr);
 
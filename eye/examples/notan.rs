@@ -11,6 +11,7 @@ use std::{
 
 use crossbeam::channel::{bounded, unbounded, Receiver, Sender};
 use eye::colorconvert::Device;
+use eye::select::Constraints;
 use eye_hal::{
     format::PixelFormat,
     stream::Descriptor,
@@ -86,11 +87,7 @@ pub fn build_textures(
 impl State {
     fn setup(gfx: &mut Graphics) -> Self {
         match try {
-            let ctx = if let Some(ctx) = PlatformContext::all().next() {
-                ctx
-            } else {
-                Err("No platform context available")?
-            };
+            let ctx = PlatformContext::default();
 
             // Create a list of valid capture devices in the system.
             let dev_descrs = ctx.devices()?;
@@ -101,27 +98,15 @@ impl State {
             // Print the supported formats for each device.
             let dev = ctx.open_device(&dev_descrs[index].uri)?;
             let dev = Device::new(dev)?;
-            let mut stream_descr = dev
-                .streams()?
-                .into_iter()
-                .filter(|desc| match desc {
-                    Descriptor {
-                        width,
-                        height,
-                        pixfmt: PixelFormat::Rgb(bits),
-                        ..
-                    } => *width == 1280 && *height == 720 && *bits == 24,
-                    _ => false,
-                })
-                .collect::<Vec<_>>();
-
-            stream_descr.sort_by(|a, b| a.interval.cmp(&b.interval));
-
-            let stream_descr = stream_descr.first().unwrap();
-
-            if stream_descr.pixfmt != PixelFormat::Rgb(24) {
-                Err("No RGB3 streams available")?
-            }
+            // Choose RGB with 8 bit depth in HD (1280 x 720) at the highest frame rate
+            let constraints = Constraints::new()
+                .resolution(1280, 720)
+                .require_pixfmt(PixelFormat::Rgb(24));
+            let selection = dev.select_stream(&constraints)?;
+            let stream_descr = match selection.best() {
+                Some(desc) => desc,
+                None => Err("No RGB3 streams available")?,
+            };
 
             println!("Selected stream:\n{:?}", stream_descr);
 
@@ -29,11 +29,11 @@ impl fmt::Debug for Repr {
 
 #[derive(Debug)]
 struct Custom {
-    _kind: ErrorKind,
+    kind: ErrorKind,
     error: Box<dyn error::Error + Send + Sync>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorKind {
     /// Invalid buffer.
     InvalidBuffer,
@@ -63,11 +63,37 @@ impl Error {
     {
         Error {
             repr: Repr::Custom(Box::new(Custom {
-                _kind: kind,
+                kind,
                 error: error.into(),
             })),
         }
     }
+
+    /// Returns the corresponding error kind
+    pub fn kind(&self) -> ErrorKind {
+        match &self.repr {
+            Repr::Simple(kind) => *kind,
+            Repr::Custom(c) => c.kind,
+        }
+    }
+
+    /// Returns a reference to the wrapped error, e.g. the one reported by a decoder
+    ///
+    /// Errors created from an [`ErrorKind`] alone do not wrap anything.
+    pub fn get_ref(&self) -> Option<&(dyn error::Error + Send + Sync + 'static)> {
+        match &self.repr {
+            Repr::Simple(_) => None,
+            Repr::Custom(c) => Some(&*c.error),
+        }
+    }
+
+    /// Consumes the error, returning the wrapped error (if any)
+    pub fn into_inner(self) -> Option<Box<dyn error::Error + Send + Sync>> {
+        match self.repr {
+            Repr::Simple(_) => None,
+            Repr::Custom(c) => Some(c.error),
+        }
+    }
 }
 
 impl From<ErrorKind> for Error {
@@ -82,7 +108,7 @@ impl From<io::Error> for Error {
     fn from(error: io::Error) -> Self {
         Error {
             repr: Repr::Custom(Box::new(Custom {
-                _kind: ErrorKind::Other,
+                kind: ErrorKind::Other,
                 error: error.into(),
             })),
         }
@@ -99,7 +125,28 @@ impl fmt::Display for Error {
 }
 
 impl error::Error for Error {
-    fn cause(&self) -> Option<&dyn std::error::Error> {
-        None
+    /// Returns the source of the wrapped error, if any
+    ///
+    /// Like [`io::Error`], the wrapped error itself is not reported as the source since its message
+    /// is what this error displays already. Use [`Error::get_ref`] to access it.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.repr {
+            Repr::Simple(_) => None,
+            Repr::Custom(c) => c.error.source(),
+        }
+    }
+}
+
+impl From<Error> for eye_hal::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error.kind() {
+            ErrorKind::InvalidBuffer | ErrorKind::InvalidParam => {
+                eye_hal::ErrorKind::InvalidArgument
+            }
+            ErrorKind::UnsupportedFormat => eye_hal::ErrorKind::UnsupportedFormat,
+            ErrorKind::Other => eye_hal::ErrorKind::Other,
+        };
+
+        eye_hal::Error::new(kind, error)
     }
 }
@@ -66,7 +66,7 @@ pub fn convert_to_rgb(src: &[u8], dst: &mut Vec<u8>) -> Result<()> {
     let mut decoder = Decoder::new(src);
     let data = match decoder.decode() {
         Ok(data) => data,
-        Err(_) => return Err(Error::new(ErrorKind::Other, "failed to decode JPEG")),
+        Err(e) => return Err(Error::new(ErrorKind::InvalidBuffer, e)),
     };
 
     let info = match decoder.info() {
@@ -79,6 +79,9 @@ pub fn convert_to_rgb(src: &[u8], dst: &mut Vec<u8>) -> Result<()> {
             *dst = data;
             Ok(())
         }
-        _ => Err(Error::new(ErrorKind::Other, "cannot handle JPEG format")),
+        _ => Err(Error::new(
+            ErrorKind::UnsupportedFormat,
+            "cannot handle JPEG format",
+        )),
     }
 }
@@ -1,4 +1,4 @@
-use eye_hal::format::PixelFormat;
+use eye_hal::format::{ImageFormat, PixelFormat, Plane};
 
 mod error;
 mod rgb;
@@ -48,4 +48,48 @@ pub struct Parameters {
     pub pixfmt: PixelFormat,
     pub width: u32,
     pub height: u32,
+    /// Memory layout of the buffers, tightly packed rows are assumed if empty
+    pub planes: Vec<Plane>,
+}
+
+impl Parameters {
+    /// Returns the image format described by the parameters
+    pub fn image(&self) -> ImageFormat {
+        let mut image = ImageFormat::new(self.width, self.height, self.pixfmt.clone());
+        if !self.planes.is_empty() {
+            image.planes = self.planes.clone();
+        }
+        image
+    }
+}
+
+/// Returns the pixel rows of the first plane of an image, without any padding
+///
+/// # Arguments
+///
+/// * `src` - Image buffer
+/// * `fmt` - Image format
+/// * `bpp` - Number of bytes per pixel
+fn rows<'a>(
+    src: &'a [u8],
+    fmt: &ImageFormat,
+    bpp: usize,
+) -> Result<impl Iterator<Item = &'a [u8]>> {
+    let plane = match fmt.planes.first() {
+        Some(plane) => *plane,
+        None => return Err(Error::from(ErrorKind::UnsupportedFormat)),
+    };
+    let (len, height) = (fmt.width as usize * bpp, fmt.height as usize);
+
+    // the padding of the last row may be missing
+    if plane.stride < len
+        || (height > 0 && src.len() < plane.offset + plane.stride * (height - 1) + len)
+    {
+        return Err(Error::from(ErrorKind::InvalidBuffer));
+    }
+
+    Ok((0..height).map(move |row| {
+        let start = plane.offset + row * plane.stride;
+        &src[start..start + len]
+    }))
 }
@@ -60,12 +60,7 @@ impl Codec for Instance {
     fn decode(&self, inbuf: &[u8], outbuf: &mut Vec<u8>) -> Result<()> {
         match (&self.inparams.pixfmt, &self.outparams.pixfmt) {
             (PixelFormat::Rgb(24), PixelFormat::Bgr(24)) => {
-                let fmt = ImageFormat {
-                    width: self.inparams.width,
-                    height: self.inparams.height,
-                    pixfmt: self.inparams.pixfmt.clone(),
-                    stride: None,
-                };
+                let fmt = self.inparams.image();
                 convert_to_bgr(inbuf, &fmt, outbuf)
             }
             _ => Err(Error::from(ErrorKind::UnsupportedFormat)),
@@ -74,14 +69,11 @@ impl Codec for Instance {
 }
 
 pub fn convert_to_bgr(src: &[u8], src_fmt: &ImageFormat, dst: &mut Vec<u8>) -> Result<()> {
-    let src_len = (src_fmt.width * src_fmt.height * 3) as usize;
+    let rows = super::rows(src, src_fmt, 3)?;
     let dst_len = (src_fmt.width * src_fmt.height * 3) as usize;
-    if src_len != src.len() {
-        return Err(Error::from(ErrorKind::InvalidBuffer));
-    }
 
     dst.resize(dst_len, 0);
-    src.iter()
+    rows.flatten()
         .copied()
         .pixels::<Rgb<u8>>()
         .colorconvert::<Bgr<u8>>()
@@ -44,10 +44,7 @@ impl Blueprint for Builder {
     }
 
     fn src_fmts(&self) -> Vec<PixelFormat> {
-        vec![
-            PixelFormat::Custom(String::from("YUYV")),
-            PixelFormat::Custom(String::from("IYU2")),
-        ]
+        vec![PixelFormat::Yuyv(16), PixelFormat::Uyv(24)]
     }
 
     fn dst_fmts(&self) -> Vec<PixelFormat> {
@@ -63,19 +60,11 @@ pub struct Instance {
 impl Codec for Instance {
     fn decode(&self, inbuf: &[u8], outbuf: &mut Vec<u8>) -> Result<()> {
         match (&self.inparams.pixfmt, &self.outparams.pixfmt) {
-            (PixelFormat::Custom(ident), PixelFormat::Rgb(24)) => {
-                let fmt = ImageFormat {
-                    width: self.inparams.width,
-                    height: self.inparams.height,
-                    pixfmt: self.inparams.pixfmt.clone(),
-                    stride: None,
-                };
-
-                match ident.as_str() {
-                    "YUYV" => yuv422_to_rgb(inbuf, &fmt, outbuf),
-                    "IYU2" => yuv444_to_rgb(inbuf, &fmt, outbuf),
-                    _ => return Err(Error::from(ErrorKind::UnsupportedFormat)),
-                }
+            (PixelFormat::Yuyv(16), PixelFormat::Rgb(24)) => {
+                yuv422_to_rgb(inbuf, &self.inparams.image(), outbuf)
+            }
+            (PixelFormat::Uyv(24), PixelFormat::Rgb(24)) => {
+                yuv444_to_rgb(inbuf, &self.inparams.image(), outbuf)
             }
             _ => Err(Error::from(ErrorKind::UnsupportedFormat)),
         }
@@ -83,16 +72,13 @@ impl Codec for Instance {
 }
 
 pub fn yuv444_to_rgb(src: &[u8], src_fmt: &ImageFormat, dst: &mut Vec<u8>) -> Result<()> {
-    let src_len = (src_fmt.width * src_fmt.height * 3) as usize;
+    let rows = super::rows(src, src_fmt, 3)?;
     let dst_len = (src_fmt.width * src_fmt.height * 3) as usize;
-    if src_len != src.len() {
-        return Err(Error::from(ErrorKind::InvalidBuffer));
-    }
 
     dst.resize(dst_len, 0);
-    src.iter()
+    rows.flatten()
         .copied()
-        .pixels::<Yuv<u8>>()
+        .pixels::<Yuv<u8, 1, 0, 2>>()
         .colorconvert::<Rgb<u8>>()
         .bytes()
         .write(dst);
@@ -101,14 +87,11 @@ pub fn yuv444_to_rgb(src: &[u8], src_fmt: &ImageFormat, dst: &mut Vec<u8>) -> Re
 }
 
 pub fn yuv422_to_rgb(src: &[u8], src_fmt: &ImageFormat, dst: &mut Vec<u8>) -> Result<()> {
-    let src_len = (src_fmt.width * src_fmt.height * 2) as usize;
+    let rows = super::rows(src, src_fmt, 2)?;
     let dst_len = (src_fmt.width * src_fmt.height * 3) as usize;
-    if src_len != src.len() {
-        return Err(Error::from(ErrorKind::InvalidBuffer));
-    }
 
     dst.resize(dst_len, 0);
-    src.iter()
+    rows.flatten()
         .copied()
         .pixels::<Yuv422<u8, 0, 2, 1, 3>>()
         .colorconvert::<[Yuv<u8>; 2]>()
@@ -1,11 +1,12 @@
 use eye_hal::error::{Error, ErrorKind, Result};
 use eye_hal::platform::Context as PlatformContext;
 use eye_hal::platform::{Device as PlatformDevice, Stream as PlatformStream};
-use eye_hal::traits::{Context, Device as DeviceTrait};
+use eye_hal::traits::{Context, Device as DeviceTrait, Stream as _};
 use eye_hal::{control, stream};
 
 use crate::colorconvert::codec;
 use crate::colorconvert::stream::CodecStream;
+use crate::select::{Candidate, Constraints, Selection};
 
 /// A transparent wrapper type for native platform devices.
 pub struct Device<'a> {
@@ -19,15 +20,39 @@ impl<'a> Device<'a> {
     }
 
     pub fn with_uri<S: AsRef<str>>(uri: S) -> Result<Self> {
-        let uri = uri.as_ref();
-        let ctx = PlatformContext::all().next().ok_or(Error::new(
-            ErrorKind::Other,
-            "no platform context available",
-        ))?;
-        let inner = ctx.open_device(uri)?;
+        let ctx = PlatformContext::default();
+        let inner = ctx.open_device(uri.as_ref())?;
 
         Self::new(inner)
     }
+
+    /// Ranks the streams of the device, including the emulated ones
+    ///
+    /// Streams are drawn from the stream ranges of the device, so frame sizes and rates between
+    /// the common ones can be selected as well. See the [`select`](crate::select) module for how
+    /// streams are scored.
+    ///
+    /// # Arguments
+    ///
+    /// * `constraints` - Requirements and preferences of the application
+    pub fn select_stream(&self, constraints: &Constraints) -> Result<Selection> {
+        let native = self.inner.stream_ranges()?;
+
+        let mut candidates: Vec<Candidate> = Vec::new();
+        for range in self.stream_ranges()? {
+            for desc in constraints.candidates(&range) {
+                if candidates.iter().any(|candidate| candidate.desc == desc) {
+                    continue;
+                }
+                candidates.push(Candidate {
+                    native: native.iter().any(|range| range.contains(&desc)),
+                    desc,
+                });
+            }
+        }
+
+        Ok(constraints.select(candidates))
+    }
 }
 
 impl<'a> DeviceTrait<'a> for Device<'a> {
@@ -70,6 +95,32 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
         Ok(streams)
     }
 
+    fn stream_ranges(&self) -> Result<Vec<stream::Range>> {
+        // get all the native ranges
+        let mut ranges = self.inner.stream_ranges()?;
+
+        // now check which formats we can emulate, same as for the streams
+        for blueprint in codec::blueprints() {
+            for chain in blueprint.src_fmts().iter().zip(blueprint.dst_fmts().iter()) {
+                if ranges.iter().any(|range| range.pixfmt == *chain.0)
+                    && !ranges.iter().any(|range| range.pixfmt == *chain.1)
+                {
+                    let emulated: Vec<stream::Range> = ranges
+                        .iter()
+                        .filter(|range| range.pixfmt == *chain.0)
+                        .map(|range| stream::Range {
+                            pixfmt: chain.1.clone(),
+                            ..range.clone()
+                        })
+                        .collect();
+                    ranges.extend(emulated);
+                }
+            }
+        }
+
+        Ok(ranges)
+    }
+
     fn start_stream(&self, desc: &stream::Descriptor) -> Result<Self::Stream> {
         let native_streams = self.inner.streams()?;
         if native_streams
@@ -98,7 +149,7 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
             pixfmt
         } else {
             return Err(Error::new(
-                ErrorKind::Other,
+                ErrorKind::UnsupportedFormat,
                 "no codec blueprint for native pixfmt",
             ));
         };
@@ -111,41 +162,37 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
             bp
         } else {
             return Err(Error::new(
-                ErrorKind::Other,
+                ErrorKind::UnsupportedFormat,
                 format!("no codec blueprint for {} -> {}", src_fmt, desc.pixfmt),
             ));
         };
 
-        // create the codec instance
+        // start the native stream with the base pixfmt
+        let mut source_fmt = desc.clone();
+        source_fmt.pixfmt = src_fmt;
+        let native_stream = self.inner.start_stream(&source_fmt)?;
+
+        // create the codec instance, the rows of the native frames may be padded
+        let native_fmt = native_stream.format();
         let inparams = codec::Parameters {
-            pixfmt: src_fmt.clone(),
-            width: desc.width,
-            height: desc.height,
+            pixfmt: native_fmt.pixfmt,
+            width: native_fmt.width,
+            height: native_fmt.height,
+            planes: native_fmt.planes,
         };
         let outparams = codec::Parameters {
             pixfmt: desc.pixfmt.clone(),
             width: desc.width,
             height: desc.height,
+            planes: Vec::new(),
         };
-        let codec = match blueprint.instantiate(inparams, outparams) {
-            Ok(instance) => instance,
-            Err(_e) => {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "failed to create codec instance",
-                ))
-            }
-        };
-
-        // start the native stream with the base pixfmt
-        let mut source_fmt = desc.clone();
-        source_fmt.pixfmt = src_fmt;
-        let native_stream = self.inner.start_stream(&source_fmt)?;
+        let codec = blueprint.instantiate(inparams, outparams)?;
 
         // create the instance that converts the frames for us
         return Ok(PlatformStream::Custom(Box::new(CodecStream {
             inner: native_stream,
             codec,
+            pixfmt: desc.pixfmt.clone(),
             buf: Vec::new(),
         })));
     }
@@ -154,6 +201,10 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
         self.inner.controls()
     }
 
+    fn control_ids(&self) -> Result<Vec<(u32, Option<control::Kind>)>> {
+        self.inner.control_ids()
+    }
+
     fn control(&self, id: u32) -> Result<control::State> {
         self.inner.control(id)
     }
@@ -161,4 +212,8 @@ impl<'a> DeviceTrait<'a> for Device<'a> {
     fn set_control(&mut self, id: u32, val: &control::State) -> Result<()> {
         self.inner.set_control(id, val)
     }
+
+    fn subscribe(&self) -> Result<control::Subscription<'a>> {
+        self.inner.subscribe()
+    }
 }
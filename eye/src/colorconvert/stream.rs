@@ -1,4 +1,8 @@
+use std::time::Duration;
+
 use eye_hal::error::Result;
+use eye_hal::format::PixelFormat;
+use eye_hal::stream::{Descriptor, Format, Frame};
 use eye_hal::traits::Stream;
 
 use crate::colorconvert::codec::Codec;
@@ -7,24 +11,42 @@ use crate::colorconvert::codec::Codec;
 pub struct CodecStream<S> {
     pub inner: S,
     pub codec: Box<dyn Codec + Send>,
+    /// Pixel format produced by the codec
+    pub pixfmt: PixelFormat,
     pub buf: Vec<u8>,
 }
 
 impl<'a, S> Stream<'a> for CodecStream<S>
 where
-    S: Stream<'a, Item = Result<&'a [u8]>>,
+    S: Stream<'a, Item = Result<Frame<'a>>>,
 {
-    type Item = Result<&'a [u8]>;
+    type Item = Result<Frame<'a>>;
+
+    fn format(&self) -> Format {
+        // the codec produces tightly packed frames, size and timing are those of the source
+        Format::packed(&Descriptor {
+            pixfmt: self.pixfmt.clone(),
+            ..self.inner.format().descriptor()
+        })
+    }
 
     fn next(&'a mut self) -> Option<Self::Item> {
         let item = self.inner.next()?;
-        let inbuf = if let Ok(buf) = item {
-            buf
-        } else {
-            return Some(item);
-        };
-
-        self.codec.decode(inbuf, &mut self.buf).unwrap();
-        Some(Ok(&self.buf))
+        Some(convert(&*self.codec, &mut self.buf, item))
     }
+
+    fn next_timeout(&'a mut self, timeout: Duration) -> Option<Self::Item> {
+        let item = self.inner.next_timeout(timeout)?;
+        Some(convert(&*self.codec, &mut self.buf, item))
+    }
+}
+
+fn convert<'a>(
+    codec: &dyn Codec,
+    buf: &'a mut Vec<u8>,
+    item: Result<Frame<'a>>,
+) -> Result<Frame<'a>> {
+    let frame = item?;
+    codec.decode(&frame, buf)?;
+    Ok(frame.with_data(&buf[..]))
 }
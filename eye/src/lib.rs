@@ -14,5 +14,8 @@
 //! conversion (e.g. JPEG -> RGB decoding) by leveraging the `colorconvert` module.
 
 pub mod colorconvert;
+pub mod profile;
+pub mod record;
+pub mod select;
 
 pub use eye_hal as hal;
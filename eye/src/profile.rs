@@ -0,0 +1,252 @@
+//! Camera profiles
+//!
+//! A profile is a snapshot of the settings of a camera: the values of all readable controls along
+//! with the format of the stream. Profiles can be applied to a device later on, e.g. to restore
+//! its settings after a reboot, which resets most cameras to their defaults.
+//!
+//! With the `serde` feature enabled, profiles can be saved in any format supported by serde, e.g.
+//! TOML or JSON. Pixel formats are stored as FourCC codes, so the documents stay readable.
+//!
+//! # Dependencies
+//!
+//! Some controls only accept values while others are in a certain state. Most notably, manual
+//! exposure, focus, white balance and gain are inactive as long as the respective automatic mode
+//! is enabled. Profiles therefore write automatic modes first and skip the controls which are
+//! inactive or not writable afterwards. Inactive controls are captured nonetheless, so their values
+//! are restored along with the respective manual mode.
+//!
+//! # Example
+//!
+//! ```
+//! use eye::profile::Profile;
+//! use eye_hal::control::{Kind, State};
+//! use eye_hal::platform::{mock, Context};
+//! use eye_hal::traits::{Context as _, Device as _, Stream as _};
+//!
+//! let ctx = Context::Mock(mock::Context::default());
+//! let mut dev = ctx.open_device("mock://0").unwrap();
+//! let desc = dev.streams().unwrap()[0].clone();
+//! let stream = dev.start_stream(&desc).unwrap();
+//!
+//! dev.set_control_by_kind(Kind::Brightness, &State::Number(64.0)).unwrap();
+//! let profile = Profile::capture(&dev, Some(&stream.format())).unwrap();
+//!
+//! dev.set_control_by_kind(Kind::Brightness, &State::Number(200.0)).unwrap();
+//! profile.apply(&mut dev).unwrap();
+//! assert_eq!(dev.control_by_kind(Kind::Brightness).unwrap(), State::Number(64.0));
+//! assert_eq!(profile.stream.unwrap().descriptor().unwrap(), desc);
+//! ```
+
+use std::convert::TryFrom;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use eye_hal::control::{self, Kind};
+use eye_hal::error::{Error, Result};
+use eye_hal::format::{FourCC, PixelFormat};
+use eye_hal::stream::{Descriptor, Format, Interval};
+use eye_hal::traits::Device;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Snapshot of the settings of a camera
+pub struct Profile {
+    /// Stream format, if any
+    pub stream: Option<Stream>,
+    /// Control values, in the order reported by the device
+    pub controls: Vec<Control>,
+}
+
+impl Profile {
+    /// Takes a snapshot of all readable controls of a device, including inactive ones
+    ///
+    /// The stream format is taken from the running stream, see [`Stream::format`].
+    ///
+    /// [`Stream::format`]: eye_hal::traits::Stream::format
+    ///
+    /// # Arguments
+    ///
+    /// * `dev` - Device to snapshot
+    /// * `format` - Format of the running stream, if any
+    pub fn capture<'a, D: Device<'a> + ?Sized>(dev: &D, format: Option<&Format>) -> Result<Self> {
+        let stream = format
+            .map(|format| Stream::try_from(&format.descriptor()))
+            .transpose()?;
+
+        let controls = dev
+            .controls()?
+            .into_iter()
+            .filter_map(|desc| match desc.value? {
+                // stateless controls (e.g. buttons) have nothing to restore
+                control::State::None => None,
+                value => Some(Control {
+                    id: desc.id,
+                    name: desc.name,
+                    value,
+                }),
+            })
+            .collect();
+
+        Ok(Profile { stream, controls })
+    }
+
+    /// Writes the control values to a device
+    ///
+    /// Automatic modes are written first. Controls which are inactive or not writable afterwards,
+    /// e.g. manual exposure while auto exposure is enabled, are skipped, as are controls the device
+    /// does not offer. All other controls are written even if some of them fail, in which case the first
+    /// error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `dev` - Device to configure
+    pub fn apply<'a, D: Device<'a> + ?Sized>(&self, dev: &mut D) -> Result<()> {
+        let mut result = Ok(());
+
+        for pass in &[Pass::Automatic, Pass::Manual] {
+            // Writing an automatic mode changes the flags of the dependent controls.
+            let descs = dev.controls()?;
+
+            for ctrl in &self.controls {
+                let desc = match descs.iter().find(|desc| desc.id == ctrl.id) {
+                    Some(desc) => desc,
+                    None => continue,
+                };
+                if Pass::of(desc.kind) != *pass || !desc.writable() || !desc.active() {
+                    continue;
+                }
+
+                if let Err(e) = dev.set_control(ctrl.id, &ctrl.value) {
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Stream format of a profile
+pub struct Stream {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// FourCC code of the pixel format, e.g. "YUYV"
+    pub pixfmt: String,
+    /// Frame interval numerator (seconds)
+    pub numerator: u32,
+    /// Frame interval denominator
+    pub denominator: u32,
+}
+
+impl Stream {
+    /// Returns the stream descriptor
+    pub fn descriptor(&self) -> Result<Descriptor> {
+        let fourcc = self.pixfmt.parse::<FourCC>()?;
+        Ok(Descriptor {
+            width: self.width,
+            height: self.height,
+            pixfmt: PixelFormat::from(fourcc),
+            interval: Interval::new(self.numerator, self.denominator),
+        })
+    }
+}
+
+impl TryFrom<&Descriptor> for Stream {
+    type Error = Error;
+
+    fn try_from(desc: &Descriptor) -> Result<Self> {
+        let fourcc = FourCC::try_from(&desc.pixfmt)?;
+        Ok(Stream {
+            width: desc.width,
+            height: desc.height,
+            pixfmt: fourcc.to_string(),
+            numerator: desc.interval.numerator,
+            denominator: desc.interval.denominator,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+/// Control value of a profile
+pub struct Control {
+    /// Unique identifier
+    pub id: u32,
+    /// Name, only informational
+    pub name: String,
+    /// Value
+    pub value: control::State,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Controls are written in passes, so dependent controls are written after the ones they depend on
+enum Pass {
+    /// Automatic modes, which determine whether the manual controls are active
+    Automatic,
+    /// Everything else
+    Manual,
+}
+
+impl Pass {
+    /// Returns the pass in which a control is written
+    fn of(kind: Option<Kind>) -> Self {
+        match kind {
+            Some(Kind::AutoExposure)
+            | Some(Kind::AutoFocus)
+            | Some(Kind::AutoWhiteBalance)
+            | Some(Kind::AutoGain) => Pass::Automatic,
+            _ => Pass::Manual,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eye_hal::platform::{mock, Context};
+    use eye_hal::traits::Context as _;
+
+    #[test]
+    fn capture_inactive_controls() {
+        let ctx = Context::Mock(mock::Context::default());
+        let mut dev = ctx.open_device("mock://0").unwrap();
+        let id = |dev: &eye_hal::platform::Device, kind| {
+            dev.control_ids()
+                .unwrap()
+                .into_iter()
+                .find(|(_, other)| *other == Some(kind))
+                .unwrap()
+                .0
+        };
+        let (auto_exposure, exposure) = (id(&dev, Kind::AutoExposure), id(&dev, Kind::Exposure));
+
+        // manual exposure is inactive while auto exposure is on, but must be captured anyway
+        dev.set_control(exposure, &control::State::Number(500.0))
+            .unwrap();
+        let mut profile = Profile::capture(&dev, None).unwrap();
+        let ctrl = profile.controls.iter().find(|ctrl| ctrl.id == exposure);
+        assert_eq!(ctrl.unwrap().value, control::State::Number(500.0));
+
+        // the value is restored along with manual mode
+        let ctrl = profile
+            .controls
+            .iter_mut()
+            .find(|ctrl| ctrl.id == auto_exposure);
+        ctrl.unwrap().value = control::State::MenuIndex(1);
+        dev.set_control(exposure, &control::State::Number(100.0))
+            .unwrap();
+        profile.apply(&mut dev).unwrap();
+        assert_eq!(
+            dev.control(exposure).unwrap(),
+            control::State::Number(500.0)
+        );
+    }
+}
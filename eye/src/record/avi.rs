@@ -0,0 +1,289 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use eye_hal::error::{Error, ErrorKind, Result};
+use eye_hal::format::PixelFormat;
+use eye_hal::stream::{Descriptor, Frame};
+
+// Offsets of the header fields which are only known once the recording is finished.
+const RIFF_SIZE: u64 = 4;
+const AVIH_TOTAL_FRAMES: u64 = 48;
+const AVIH_SUGGESTED_BUFFER_SIZE: u64 = 60;
+const STRH_LENGTH: u64 = 140;
+const STRH_SUGGESTED_BUFFER_SIZE: u64 = 144;
+const MOVI_SIZE: u64 = 216;
+/// Offset of the 'movi' fourcc, chunk offsets in the index are relative to it
+const MOVI_START: u64 = 220;
+
+/// AVIF_HASINDEX
+const AVIF_HASINDEX: u32 = 0x10;
+/// AVIIF_KEYFRAME
+const AVIIF_KEYFRAME: u32 = 0x10;
+
+/// AVI 1.0 writer for MJPEG streams
+///
+/// The header is written with placeholder values first and patched once the recording is
+/// finished.
+pub struct Writer {
+    file: BufWriter<File>,
+    /// Offset and length of each frame chunk
+    index: Vec<(u32, u32)>,
+    /// Current write position
+    pos: u64,
+    max_frame_size: u32,
+}
+
+impl Writer {
+    pub fn new(path: &Path, desc: &Descriptor) -> Result<Self> {
+        if desc.pixfmt != PixelFormat::Jpeg {
+            return Err(Error::new(
+                ErrorKind::UnsupportedFormat,
+                format!("cannot record {} frames to AVI", desc.pixfmt),
+            ));
+        }
+
+        let (rate, scale) = super::frame_rate(desc);
+        let us_per_frame = desc.interval.as_duration().as_micros() as u32;
+        let (width, height) = (desc.width, desc.height);
+
+        let mut header = Vec::with_capacity(MOVI_START as usize + 4);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(b"AVI ");
+
+        // LIST hdrl
+        header.extend_from_slice(b"LIST");
+        header.extend_from_slice(&192u32.to_le_bytes());
+        header.extend_from_slice(b"hdrl");
+        header.extend_from_slice(b"avih");
+        header.extend_from_slice(&56u32.to_le_bytes());
+        for val in [
+            us_per_frame,
+            0, // max bytes per second
+            0, // padding granularity
+            AVIF_HASINDEX,
+            0, // total frames
+            0, // initial frames
+            1, // streams
+            0, // suggested buffer size
+            width,
+            height,
+            0,
+            0,
+            0,
+            0,
+        ] {
+            header.extend_from_slice(&val.to_le_bytes());
+        }
+
+        // LIST strl
+        header.extend_from_slice(b"LIST");
+        header.extend_from_slice(&116u32.to_le_bytes());
+        header.extend_from_slice(b"strl");
+        header.extend_from_slice(b"strh");
+        header.extend_from_slice(&56u32.to_le_bytes());
+        header.extend_from_slice(b"vids");
+        header.extend_from_slice(b"MJPG");
+        for val in [
+            0, // flags
+            0, // priority, language
+            0, // initial frames
+            scale,
+            rate,
+            0,        // start
+            0,        // length
+            0,        // suggested buffer size
+            u32::MAX, // quality (default)
+            0,        // sample size
+            0,        // frame rectangle (left, top)
+            width & 0xffff | (height & 0xffff) << 16,
+        ] {
+            header.extend_from_slice(&val.to_le_bytes());
+        }
+        header.extend_from_slice(b"strf");
+        header.extend_from_slice(&40u32.to_le_bytes());
+        header.extend_from_slice(&40u32.to_le_bytes());
+        header.extend_from_slice(&width.to_le_bytes());
+        header.extend_from_slice(&height.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes()); // planes
+        header.extend_from_slice(&24u16.to_le_bytes()); // bit count
+        header.extend_from_slice(b"MJPG");
+        header.extend_from_slice(&(width * height * 3).to_le_bytes());
+        header.extend_from_slice(&[0u8; 16]);
+
+        // LIST movi
+        header.extend_from_slice(b"LIST");
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(b"movi");
+        debug_assert_eq!(header.len() as u64, MOVI_START + 4);
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&header)?;
+
+        Ok(Writer {
+            file,
+            index: Vec::new(),
+            pos: header.len() as u64,
+            max_frame_size: 0,
+        })
+    }
+
+    pub fn write(&mut self, frame: &Frame) -> Result<()> {
+        let len = frame.len() as u64;
+        let padding = len % 2;
+
+        // AVI 1.0 uses 32 bit sizes, leave some room for the index as well
+        let end = self.pos + 8 + len + padding + (self.index.len() as u64 + 1) * 16 + 8;
+        if end > u32::MAX as u64 {
+            return Err(Error::new(ErrorKind::Other, "AVI file size limit reached"));
+        }
+
+        self.file.write_all(b"00dc")?;
+        self.file.write_all(&(len as u32).to_le_bytes())?;
+        self.file.write_all(frame)?;
+        if padding > 0 {
+            self.file.write_all(&[0])?;
+        }
+
+        self.index
+            .push(((self.pos - MOVI_START) as u32, len as u32));
+        self.max_frame_size = self.max_frame_size.max(len as u32);
+        self.pos += 8 + len + padding;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        let movi_size = (self.pos - MOVI_START) as u32;
+
+        self.file.write_all(b"idx1")?;
+        self.file
+            .write_all(&(self.index.len() as u32 * 16).to_le_bytes())?;
+        for (offset, len) in &self.index {
+            self.file.write_all(b"00dc")?;
+            self.file.write_all(&AVIIF_KEYFRAME.to_le_bytes())?;
+            self.file.write_all(&offset.to_le_bytes())?;
+            self.file.write_all(&len.to_le_bytes())?;
+        }
+        let riff_size = (self.pos + 8 + self.index.len() as u64 * 16 - 8) as u32;
+
+        let frames = self.index.len() as u32;
+        for (offset, val) in [
+            (RIFF_SIZE, riff_size),
+            (AVIH_TOTAL_FRAMES, frames),
+            (AVIH_SUGGESTED_BUFFER_SIZE, self.max_frame_size),
+            (STRH_LENGTH, frames),
+            (STRH_SUGGESTED_BUFFER_SIZE, self.max_frame_size),
+            (MOVI_SIZE, movi_size),
+        ] {
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.write_all(&val.to_le_bytes())?;
+        }
+
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eye_hal::platform::{file, mock, Context};
+    use eye_hal::stream::Interval;
+    use eye_hal::traits::{Context as _, Device as _, Stream as _};
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    }
+
+    /// Captures JPEG frames from a mock device
+    fn frames(count: usize) -> (Descriptor, Vec<Vec<u8>>) {
+        let ctx = Context::Mock(mock::Context::default());
+        let dev = ctx.open_device("mock://0").unwrap();
+        let desc = dev
+            .streams()
+            .unwrap()
+            .into_iter()
+            .find(|desc| desc.pixfmt == PixelFormat::Jpeg)
+            .unwrap();
+        let mut stream = dev.start_stream(&desc).unwrap();
+        let frames = (0..count)
+            .map(|_| stream.next().unwrap().unwrap().to_vec())
+            .collect();
+        (desc, frames)
+    }
+
+    #[test]
+    fn roundtrip() {
+        // the AVI container is transparent to the MJPEG scanner of the file backend
+        let path = std::env::temp_dir().join(format!("eye-avi-{}.mjpeg", std::process::id()));
+        let (desc, frames) = frames(3);
+
+        let mut writer = Writer::new(&path, &desc).unwrap();
+        for frame in &frames {
+            writer.write(&Frame::new(&frame[..])).unwrap();
+        }
+        writer.finish().unwrap();
+        let data = std::fs::read(&path).unwrap();
+
+        let ctx = Context::File(file::Context {});
+        let uri = format!("file://{}?realtime=false", path.display());
+        let dev = ctx.open_device(&uri).unwrap();
+        let played_desc = dev.streams().unwrap()[0].clone();
+        let mut stream = dev.start_stream(&played_desc).unwrap();
+        let mut played = Vec::new();
+        while let Some(frame) = stream.next() {
+            played.push(frame.unwrap().to_vec());
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            (played_desc.width, played_desc.height),
+            (desc.width, desc.height)
+        );
+        assert_eq!(played, frames);
+
+        // header fields patched by finish()
+        let max_len = frames.iter().map(|frame| frame.len()).max().unwrap() as u32;
+        assert_eq!(&data[..4], b"RIFF");
+        assert_eq!(u32_at(&data, RIFF_SIZE as usize), data.len() as u32 - 8);
+        assert_eq!(u32_at(&data, AVIH_TOTAL_FRAMES as usize), 3);
+        assert_eq!(u32_at(&data, STRH_LENGTH as usize), 3);
+        assert_eq!(u32_at(&data, AVIH_SUGGESTED_BUFFER_SIZE as usize), max_len);
+        assert_eq!(u32_at(&data, STRH_SUGGESTED_BUFFER_SIZE as usize), max_len);
+
+        // the index points at the frame chunks
+        let idx = MOVI_START as usize + u32_at(&data, MOVI_SIZE as usize) as usize;
+        assert_eq!(&data[idx..idx + 4], b"idx1");
+        assert_eq!(u32_at(&data, idx + 4), 3 * 16);
+        for (i, frame) in frames.iter().enumerate() {
+            let entry = idx + 8 + i * 16;
+            let (offset, len) = (u32_at(&data, entry + 8), u32_at(&data, entry + 12));
+            let chunk = MOVI_START as usize + offset as usize;
+            assert_eq!(&data[chunk..chunk + 4], b"00dc");
+            assert_eq!(len as usize, frame.len());
+            assert_eq!(&data[chunk + 8..chunk + 8 + frame.len()], &frame[..]);
+        }
+    }
+
+    #[test]
+    fn uncompressed() {
+        let path = std::env::temp_dir().join(format!("eye-avi-raw-{}.avi", std::process::id()));
+        let desc = Descriptor {
+            width: 4,
+            height: 2,
+            pixfmt: PixelFormat::Yuyv(16),
+            interval: Interval::from_fps(30),
+        };
+
+        let res = Writer::new(&path, &desc);
+        assert!(matches!(res, Err(e) if e.kind() == ErrorKind::UnsupportedFormat));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,174 @@
+//! Stream recording
+//!
+//! Frames captured from a stream can be written to disk in one of several containers:
+//!
+//! * [`Container::Y4m`] - YUV4MPEG2 for uncompressed footage. Packed YUV and RGB formats are
+//!   converted to planar YUV on the fly.
+//! * [`Container::Avi`] - AVI (RIFF) for MJPEG footage. Frames are passed through without
+//!   re-encoding.
+//! * [`Container::Raw`] - Concatenated frames in their native format. A sidecar index file
+//!   (`<path>.idx`) describes the format as well as the position and timestamp of each frame.
+//!
+//! The frame size, format and rate of a recording are taken from the stream format. Rows of
+//! uncompressed frames may be padded, e.g. to satisfy alignment requirements of the driver, so
+//! prefer [`Recorder::with_format`] with the format reported by the stream over
+//! [`Recorder::new`], which assumes tightly packed frames.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use eye::record::{Container, Recorder};
+//! use eye_hal::traits::{Context, Device, Stream};
+//! use eye_hal::PlatformContext;
+//!
+//! let ctx = PlatformContext::default();
+//! let devices = ctx.devices().expect("Failed to query devices");
+//! let dev = ctx.open_device(&devices[0].uri).expect("Failed to open device");
+//! let desc = dev.streams().expect("Failed to query streams")[0].clone();
+//! let mut stream = dev.start_stream(&desc).expect("Failed to start stream");
+//!
+//! let mut recorder = Recorder::with_format("capture.y4m", Container::Y4m, &stream.format()).unwrap();
+//! recorder.record(&mut stream, 100).expect("Failed to record frames");
+//! recorder.finish().expect("Failed to finish recording");
+//! ```
+
+mod avi;
+mod raw;
+mod y4m;
+
+use std::path::Path;
+
+use eye_hal::error::Result;
+use eye_hal::stream::{Descriptor, Format, Frame};
+use eye_hal::traits::Stream;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Recording container format
+pub enum Container {
+    /// YUV4MPEG2 (uncompressed formats only)
+    Y4m,
+    /// Audio Video Interleave (MJPEG only)
+    Avi,
+    /// Raw frames with a sidecar index
+    Raw,
+}
+
+enum Writer {
+    Y4m(y4m::Writer),
+    Avi(avi::Writer),
+    Raw(raw::Writer),
+}
+
+/// Writes frames to a file
+///
+/// The recording is finalized when the recorder is dropped, but errors can only be observed by
+/// calling [`Recorder::finish`] explicitly.
+pub struct Recorder {
+    writer: Option<Writer>,
+}
+
+impl Recorder {
+    /// Creates a new recording of tightly packed frames
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Output file path
+    /// * `container` - Container format
+    /// * `desc` - Descriptor of the recorded stream
+    pub fn new<P: AsRef<Path>>(path: P, container: Container, desc: &Descriptor) -> Result<Self> {
+        Self::with_format(path, container, &Format::packed(desc))
+    }
+
+    /// Creates a new recording
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Output file path
+    /// * `container` - Container format
+    /// * `format` - Format of the recorded stream, see [`Stream::format`]
+    pub fn with_format<P: AsRef<Path>>(
+        path: P,
+        container: Container,
+        format: &Format,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let desc = format.descriptor();
+        let writer = match container {
+            Container::Y4m => Writer::Y4m(y4m::Writer::new(path, format)?),
+            Container::Avi => Writer::Avi(avi::Writer::new(path, &desc)?),
+            Container::Raw => Writer::Raw(raw::Writer::new(path, &desc)?),
+        };
+
+        Ok(Recorder {
+            writer: Some(writer),
+        })
+    }
+
+    /// Writes a single frame
+    pub fn write(&mut self, frame: &Frame) -> Result<()> {
+        match self.writer.as_mut() {
+            Some(Writer::Y4m(writer)) => writer.write(frame),
+            Some(Writer::Avi(writer)) => writer.write(frame),
+            Some(Writer::Raw(writer)) => writer.write(frame),
+            None => unreachable!("writer is only taken when finishing"),
+        }
+    }
+
+    /// Writes frames from a stream until `count` frames were written or the stream ends
+    ///
+    /// Returns the number of frames written.
+    pub fn record<S>(&mut self, stream: &mut S, count: usize) -> Result<usize>
+    where
+        S: for<'a> Stream<'a, Item = Result<Frame<'a>>>,
+    {
+        for i in 0..count {
+            match stream.next() {
+                Some(Ok(frame)) => self.write(&frame)?,
+                Some(Err(e)) => return Err(e),
+                None => return Ok(i),
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Flushes all data and finalizes the container
+    pub fn finish(mut self) -> Result<()> {
+        self.finalize()
+    }
+
+    fn finalize(&mut self) -> Result<()> {
+        match self.writer.take() {
+            Some(Writer::Y4m(writer)) => writer.finish(),
+            Some(Writer::Avi(writer)) => writer.finish(),
+            Some(Writer::Raw(writer)) => writer.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // ignore the result
+        let _ = self.finalize();
+    }
+}
+
+/// Returns the frame rate of a stream as a reduced fraction
+fn frame_rate(desc: &Descriptor) -> (u32, u32) {
+    let (num, den) = (desc.interval.denominator, desc.interval.numerator);
+    if num == 0 || den == 0 {
+        return (0, 1);
+    }
+
+    let gcd = gcd(num, den);
+    (num / gcd, den / gcd)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a.max(1)
+    } else {
+        gcd(b, a % b)
+    }
+}
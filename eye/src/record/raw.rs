@@ -0,0 +1,107 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use eye_hal::error::Result;
+use eye_hal::format::FourCC;
+use eye_hal::stream::{Descriptor, Frame};
+
+/// Raw frame writer
+///
+/// Frames are written as they are. The index file is a plain text file: the first line contains
+/// the stream format as `file://` URI query (so the recording can be played back right away by
+/// appending it to the URI of the frame file), followed by one line per frame:
+///
+/// ```text
+/// width=640&height=480&format=YUYV&fps=30000/1001
+/// <sequence> <timestamp in ns or -> <offset> <length>
+/// ```
+pub struct Writer {
+    file: BufWriter<File>,
+    index: BufWriter<File>,
+    offset: u64,
+}
+
+impl Writer {
+    pub fn new(path: &Path, desc: &Descriptor) -> Result<Self> {
+        // the format is written as four character code, so playback can map it back
+        let fourcc = FourCC::try_from(&desc.pixfmt)?;
+
+        let mut index_path = path.as_os_str().to_owned();
+        index_path.push(".idx");
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut index = BufWriter::new(File::create(index_path)?);
+        let (num, den) = super::frame_rate(desc);
+        writeln!(
+            index,
+            "width={}&height={}&format={}&fps={}/{}",
+            desc.width, desc.height, fourcc, num, den
+        )?;
+
+        Ok(Writer {
+            file,
+            index,
+            offset: 0,
+        })
+    }
+
+    pub fn write(&mut self, frame: &Frame) -> Result<()> {
+        self.file.write_all(frame)?;
+
+        match frame.timestamp {
+            Some(timestamp) => write!(self.index, "{} {} ", frame.sequence, timestamp.as_nanos())?,
+            None => write!(self.index, "{} - ", frame.sequence)?,
+        }
+        writeln!(self.index, "{} {}", self.offset, frame.len())?;
+
+        self.offset += frame.len() as u64;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.file.flush()?;
+        self.index.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eye_hal::format::PixelFormat;
+    use eye_hal::platform::{file, Context};
+    use eye_hal::stream::Interval;
+    use eye_hal::traits::{Context as _, Device as _};
+
+    #[test]
+    fn index_header_is_uri_query() {
+        let path = std::env::temp_dir().join(format!("eye-raw-{}.raw", std::process::id()));
+        let desc = Descriptor {
+            width: 4,
+            height: 2,
+            pixfmt: PixelFormat::Gray(8),
+            interval: Interval::new(1001, 30000),
+        };
+
+        let mut writer = Writer::new(&path, &desc).unwrap();
+        writer.write(&Frame::new(&[0u8; 8][..])).unwrap();
+        writer.finish().unwrap();
+
+        let mut index_path = path.as_os_str().to_owned();
+        index_path.push(".idx");
+        let index = std::fs::read_to_string(&index_path).unwrap();
+        let query = index.lines().next().unwrap();
+        assert_eq!(query, "width=4&height=2&format=GREY&fps=30000/1001");
+
+        // the recording plays back with the exact interval it was recorded with
+        let ctx = Context::File(file::Context {});
+        let uri = format!("file://{}?{}", path.display(), query);
+        let streams = ctx.open_device(&uri).unwrap().streams().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&index_path).unwrap();
+        assert_eq!(streams, [desc]);
+    }
+}
@@ -0,0 +1,352 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use eye_hal::error::{Error, ErrorKind, Result};
+use eye_hal::format::{ImageFormat, PixelFormat, Plane};
+use eye_hal::stream::{Format, Frame};
+
+/// Layout of the frames as stored in the file
+enum Layout {
+    /// Planes are written as they are, described by the length and number of their rows
+    Planar(Vec<(usize, usize)>),
+    /// Packed 4:2:2 frames (YUYV, UYVY, ...) converted to planar 4:2:2
+    Packed422 { y: usize, u: usize, v: usize },
+    /// Packed RGB frames converted to planar 4:4:4
+    Rgb {
+        r: usize,
+        g: usize,
+        b: usize,
+        bpp: usize,
+    },
+}
+
+pub struct Writer {
+    file: BufWriter<File>,
+    width: usize,
+    height: usize,
+    layout: Layout,
+    /// Memory layout of the input frames
+    planes: Vec<Plane>,
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new(path: &Path, format: &Format) -> Result<Self> {
+        let desc = format.descriptor();
+        let (width, height) = (desc.width as usize, desc.height as usize);
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+
+        let (colorspace, layout) = match &desc.pixfmt {
+            PixelFormat::Gray(8) => ("mono", Layout::Planar(vec![(width, height)])),
+            PixelFormat::Rgb(24) => (
+                "444",
+                Layout::Rgb {
+                    r: 0,
+                    g: 1,
+                    b: 2,
+                    bpp: 3,
+                },
+            ),
+            PixelFormat::Rgb(32) | PixelFormat::Rgba(32) => (
+                "444",
+                Layout::Rgb {
+                    r: 0,
+                    g: 1,
+                    b: 2,
+                    bpp: 4,
+                },
+            ),
+            PixelFormat::Bgr(24) => (
+                "444",
+                Layout::Rgb {
+                    r: 2,
+                    g: 1,
+                    b: 0,
+                    bpp: 3,
+                },
+            ),
+            PixelFormat::Bgr(32) | PixelFormat::Bgra(32) => (
+                "444",
+                Layout::Rgb {
+                    r: 2,
+                    g: 1,
+                    b: 0,
+                    bpp: 4,
+                },
+            ),
+            PixelFormat::I420(12) => (
+                "420jpeg",
+                Layout::Planar(vec![
+                    (width, height),
+                    (chroma_width, chroma_height),
+                    (chroma_width, chroma_height),
+                ]),
+            ),
+            // packed 4:2:2 formats store two pixels per macropixel
+            PixelFormat::Yuyv(16) if width & 1 == 0 => {
+                ("422", Layout::Packed422 { y: 0, u: 1, v: 3 })
+            }
+            PixelFormat::Uyvy(16) if width & 1 == 0 => {
+                ("422", Layout::Packed422 { y: 1, u: 0, v: 2 })
+            }
+            PixelFormat::Yvyu(16) if width & 1 == 0 => {
+                ("422", Layout::Packed422 { y: 0, u: 3, v: 1 })
+            }
+            PixelFormat::Vyuy(16) if width & 1 == 0 => {
+                ("422", Layout::Packed422 { y: 1, u: 2, v: 0 })
+            }
+            PixelFormat::I422(16) => (
+                "422",
+                Layout::Planar(vec![
+                    (width, height),
+                    (chroma_width, height),
+                    (chroma_width, height),
+                ]),
+            ),
+            PixelFormat::I444(24) => (
+                "444",
+                Layout::Planar(vec![(width, height), (width, height), (width, height)]),
+            ),
+            _ => return Err(unsupported(&desc.pixfmt)),
+        };
+
+        // streams which do not report their layout produce tightly packed frames
+        let planes = if format.planes.is_empty() {
+            ImageFormat::new(desc.width, desc.height, desc.pixfmt.clone()).planes
+        } else {
+            format.planes.clone()
+        };
+        let count = match &layout {
+            Layout::Planar(rows) => rows.len(),
+            _ => 1,
+        };
+        if planes.len() < count {
+            return Err(unsupported(&desc.pixfmt));
+        }
+
+        let (num, den) = super::frame_rate(&desc);
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(
+            file,
+            "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C{}",
+            width, height, num, den, colorspace
+        )?;
+
+        Ok(Writer {
+            file,
+            width,
+            height,
+            layout,
+            planes,
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn write(&mut self, frame: &Frame) -> Result<()> {
+        let (width, height) = (self.width, self.height);
+        let pixels = width * height;
+        self.buf.clear();
+
+        // Rows may be padded, so they are copied one by one.
+        match &self.layout {
+            Layout::Planar(planes) => {
+                for (plane, (len, count)) in self.planes.iter().zip(planes) {
+                    for row in rows(frame, plane, *len, *count)? {
+                        self.buf.extend_from_slice(row);
+                    }
+                }
+            }
+            Layout::Packed422 { y, u, v } => {
+                let (y, u, v) = (*y, *u, *v);
+                self.buf.resize(pixels * 2, 0);
+                let (luma, chroma) = self.buf.split_at_mut(pixels);
+                let (cb, cr) = chroma.split_at_mut(pixels / 2);
+
+                // every macropixel (4 bytes) holds two luma and one pair of chroma samples
+                let macropixels = rows(frame, &self.planes[0], width * 2, height)?
+                    .flat_map(|row| row.chunks_exact(4));
+                for (i, px) in macropixels.enumerate() {
+                    luma[i * 2] = px[y];
+                    luma[i * 2 + 1] = px[y + 2];
+                    cb[i] = px[u];
+                    cr[i] = px[v];
+                }
+            }
+            Layout::Rgb { r, g, b, bpp } => {
+                let (r, g, b, bpp) = (*r, *g, *b, *bpp);
+                self.buf.resize(pixels * 3, 0);
+                let (luma, chroma) = self.buf.split_at_mut(pixels);
+                let (cb, cr) = chroma.split_at_mut(pixels);
+
+                let pixels = rows(frame, &self.planes[0], width * bpp, height)?
+                    .flat_map(|row| row.chunks_exact(bpp));
+                for (i, px) in pixels.enumerate() {
+                    // BT.601 limited range
+                    let (r, g, b) = (px[r] as i32, px[g] as i32, px[b] as i32);
+                    luma[i] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+                    cb[i] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+                    cr[i] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+                }
+            }
+        }
+
+        self.file.write_all(b"FRAME\n")?;
+        self.file.write_all(&self.buf)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+fn unsupported(pixfmt: &PixelFormat) -> Error {
+    Error::new(
+        ErrorKind::UnsupportedFormat,
+        format!("cannot record {} frames to Y4M", pixfmt),
+    )
+}
+
+/// Returns the rows of a plane without their padding
+///
+/// # Arguments
+///
+/// * `frame` - Image buffer
+/// * `plane` - Memory layout of the plane
+/// * `len` - Number of bytes per row, excluding padding
+/// * `count` - Number of rows
+fn rows<'a>(
+    frame: &'a [u8],
+    plane: &Plane,
+    len: usize,
+    count: usize,
+) -> Result<impl Iterator<Item = &'a [u8]>> {
+    let plane = *plane;
+
+    // the padding of the last row may be missing
+    if plane.stride < len
+        || (count > 0 && frame.len() < plane.offset + plane.stride * (count - 1) + len)
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidArgument,
+            format!("invalid frame size: {}", frame.len()),
+        ));
+    }
+
+    Ok((0..count).map(move |row| {
+        let start = plane.offset + row * plane.stride;
+        &frame[start..start + len]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eye_hal::platform::{file, Context};
+    use eye_hal::stream::{Descriptor, Interval};
+    use eye_hal::traits::{Context as _, Device as _, Stream as _};
+
+    /// Writes frames to a Y4M file and plays them back through the file backend
+    fn roundtrip(name: &str, format: &Format, frames: &[Vec<u8>]) -> (Descriptor, Vec<Vec<u8>>) {
+        let path =
+            std::env::temp_dir().join(format!("eye-y4m-{}-{}.y4m", name, std::process::id()));
+
+        let mut writer = Writer::new(&path, format).unwrap();
+        for frame in frames {
+            writer.write(&Frame::new(&frame[..])).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let ctx = Context::File(file::Context {});
+        let uri = format!("file://{}?realtime=false", path.display());
+        let dev = ctx.open_device(&uri).unwrap();
+        let desc = dev.streams().unwrap()[0].clone();
+        let mut stream = dev.start_stream(&desc).unwrap();
+        let mut played = Vec::new();
+        while let Some(frame) = stream.next() {
+            played.push(frame.unwrap().to_vec());
+        }
+
+        std::fs::remove_file(&path).unwrap();
+        (desc, played)
+    }
+
+    #[test]
+    fn header() {
+        let path = std::env::temp_dir().join(format!("eye-y4m-header-{}.y4m", std::process::id()));
+        let format = Format::packed(&Descriptor {
+            width: 4,
+            height: 2,
+            pixfmt: PixelFormat::Gray(8),
+            interval: Interval::new(1001, 30000),
+        });
+
+        let mut writer = Writer::new(&path, &format).unwrap();
+        writer.write(&Frame::new(&[7u8; 8][..])).unwrap();
+        writer.finish().unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut expected = b"YUV4MPEG2 W4 H2 F30000:1001 Ip A1:1 Cmono\nFRAME\n".to_vec();
+        expected.extend_from_slice(&[7u8; 8]);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn planar() {
+        let desc = Descriptor {
+            width: 4,
+            height: 2,
+            pixfmt: PixelFormat::I420(12),
+            interval: Interval::from_fps(25),
+        };
+        let frames: Vec<Vec<u8>> = (0..3u8).map(|i| (i * 12..i * 12 + 12).collect()).collect();
+
+        let (played_desc, played) = roundtrip("planar", &Format::packed(&desc), &frames);
+        assert_eq!(played_desc, desc);
+        assert_eq!(played, frames);
+    }
+
+    #[test]
+    fn padded_packed() {
+        // 2x2 YUYV frame with rows padded to 8 bytes
+        let format = Format::packed(&Descriptor {
+            width: 2,
+            height: 2,
+            pixfmt: PixelFormat::Yuyv(16),
+            interval: Interval::from_fps(30),
+        });
+        let format = Format {
+            stride: 8,
+            planes: ImageFormat::new(2, 2, PixelFormat::Yuyv(16))
+                .stride(8)
+                .planes,
+            ..format
+        };
+        let frame = vec![1, 10, 2, 20, 0, 0, 0, 0, 3, 30, 4, 40, 0, 0, 0, 0];
+
+        let (desc, played) = roundtrip("padded", &format, &[frame]);
+        assert_eq!(desc.pixfmt, PixelFormat::I422(16));
+        // Y plane, then U and V planes subsampled horizontally
+        assert_eq!(played, [vec![1, 2, 3, 4, 10, 30, 20, 40]]);
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let path = std::env::temp_dir().join(format!("eye-y4m-short-{}.y4m", std::process::id()));
+        let format = Format::packed(&Descriptor {
+            width: 4,
+            height: 2,
+            pixfmt: PixelFormat::Rgb(24),
+            interval: Interval::from_fps(30),
+        });
+
+        let mut writer = Writer::new(&path, &format).unwrap();
+        let err = writer.write(&Frame::new(&[0u8; 23][..])).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
+}
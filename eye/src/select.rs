@@ -0,0 +1,589 @@
+//! Stream selection
+//!
+//! Devices usually offer dozens of streams which differ in frame size, rate and pixel format.
+//! Instead of picking one by hand, applications describe what they need in terms of
+//! [`Constraints`] and let the device rank its streams, see
+//! [`Device::select_stream`](crate::colorconvert::Device::select_stream).
+//!
+//! # Scoring
+//!
+//! Candidates which violate a hard constraint (minimum resolution, aspect ratio, required pixel
+//! format or conversion) are rejected and the reasons are reported alongside. The remaining candidates are
+//! ranked by comparing the following criteria in order, the first difference decides:
+//!
+//! 1. Resolution: the distance `|width - preferred width| + |height - preferred height|`, smaller
+//!    is better. Larger frames win on ties or if there is no preferred resolution.
+//! 2. Frame rate: the distance to the target frame rate, smaller is better. Higher rates win on
+//!    ties or if there is no target frame rate.
+//! 3. Pixel format: the position in the list of preferred formats, earlier is better. Formats
+//!    which are not in the list come last.
+//! 4. Native streams win over converted ones.
+//!
+//! Candidates which are still equal keep the order in which the device reported them.
+//!
+//! # Ranges
+//!
+//! Devices which support arbitrary frame sizes or rates within some bounds describe their streams
+//! as [`Range`]s. Such ranges are represented by [`Constraints::candidates`], which adds the
+//! stream closest to the preferred resolution and frame rate to a set of common streams.
+//!
+//! # Example
+//!
+//! ```
+//! use eye::colorconvert::Device;
+//! use eye::select::{Constraints, Reason};
+//! use eye_hal::format::PixelFormat;
+//! use eye_hal::platform::{mock, Context};
+//! use eye_hal::traits::Context as _;
+//!
+//! let ctx = Context::Mock(mock::Context::default());
+//! let dev = Device::new(ctx.open_device("mock://0").unwrap()).unwrap();
+//!
+//! let constraints = Constraints::new()
+//!     .resolution(1280, 720)
+//!     .fps(30)
+//!     .require_pixfmt(PixelFormat::Rgb(24));
+//! let selection = dev.select_stream(&constraints).unwrap();
+//! let best = selection.best().unwrap();
+//! assert_eq!((best.width, best.height), (1280, 720));
+//! assert_eq!(best.pixfmt, PixelFormat::Rgb(24));
+//!
+//! // the mock camera does not offer RGB natively
+//! let selection = dev.select_stream(&constraints.conversion(false)).unwrap();
+//! assert!(selection.best().is_none());
+//! assert!(selection
+//!     .rejected
+//!     .iter()
+//!     .any(|rejection| rejection.reasons.contains(&Reason::Conversion)));
+//! ```
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use eye_hal::format::PixelFormat;
+use eye_hal::stream::{Bounds, Descriptor, Interval, Range};
+
+/// Deviation from the requested aspect ratio which is still accepted, in percent
+///
+/// Some frame sizes only approximate their nominal aspect ratio, e.g. 854x480 for 16:9.
+const ASPECT_RATIO_TOLERANCE: u64 = 1;
+
+#[derive(Clone, Debug)]
+/// Requirements and preferences for stream selection
+pub struct Constraints {
+    /// Minimum frame size (width, height), smaller streams are rejected
+    pub min_resolution: Option<(u32, u32)>,
+    /// Preferred frame size (width, height)
+    pub resolution: Option<(u32, u32)>,
+    /// Preferred frame interval
+    pub interval: Option<Interval>,
+    /// Required aspect ratio (width, height), e.g. (16, 9)
+    pub aspect_ratio: Option<(u32, u32)>,
+    /// Preferred pixel formats, most preferred first
+    pub pixfmts: Vec<PixelFormat>,
+    /// Acceptable pixel formats, streams in other formats are rejected; all formats are accepted
+    /// if empty
+    pub required_pixfmts: Vec<PixelFormat>,
+    /// Whether streams which are converted from a native format are accepted
+    pub conversion: bool,
+}
+
+impl Constraints {
+    /// Returns constraints which accept any stream, including converted ones
+    pub fn new() -> Self {
+        Constraints {
+            min_resolution: None,
+            resolution: None,
+            interval: None,
+            aspect_ratio: None,
+            pixfmts: Vec::new(),
+            required_pixfmts: Vec::new(),
+            conversion: true,
+        }
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Minimum width in pixels
+    /// * `height` - Minimum height in pixels
+    pub fn min_resolution(mut self, width: u32, height: u32) -> Self {
+        self.min_resolution = Some((width, height));
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Preferred width in pixels
+    /// * `height` - Preferred height in pixels
+    pub fn resolution(mut self, width: u32, height: u32) -> Self {
+        self.resolution = Some((width, height));
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `fps` - Target frame rate
+    pub fn fps(self, fps: u32) -> Self {
+        self.interval(Interval::from_fps(fps))
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Target frame interval, e.g. 1001/30000 for 29.97 fps
+    pub fn interval(mut self, interval: Interval) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Horizontal part of the ratio
+    /// * `height` - Vertical part of the ratio
+    pub fn aspect_ratio(mut self, width: u32, height: u32) -> Self {
+        self.aspect_ratio = Some((width, height));
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// Formats are preferred in the order they are added. Streams in other formats are still
+    /// accepted, see [`Constraints::require_pixfmt`] to reject them.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixfmt` - Preferred pixel format
+    pub fn pixfmt(mut self, pixfmt: PixelFormat) -> Self {
+        self.pixfmts.push(pixfmt);
+        self
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// Streams in formats which were not added are rejected. The format is preferred as well, in
+    /// the order of the calls to [`Constraints::pixfmt`] and this function.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixfmt` - Acceptable pixel format
+    pub fn require_pixfmt(mut self, pixfmt: PixelFormat) -> Self {
+        self.required_pixfmts.push(pixfmt.clone());
+        self.pixfmt(pixfmt)
+    }
+
+    /// Builder pattern constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `allow` - Whether to accept streams which are converted from a native format
+    pub fn conversion(mut self, allow: bool) -> Self {
+        self.conversion = allow;
+        self
+    }
+
+    /// Returns the streams of a range which are worth considering
+    ///
+    /// These are the streams returned by [`Range::descriptors`] along with the one closest to
+    /// the preferred resolution and frame rate, which may well lie between the common sizes.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - Range of streams supported by a device
+    pub fn candidates(&self, range: &Range) -> Vec<Descriptor> {
+        let (width, height) = match self.resolution {
+            Some((width, height)) => (nearest(&range.width, width), nearest(&range.height, height)),
+            None => (range.width.max, range.height.max),
+        };
+        let interval = match self.interval {
+            Some(interval) => interval.max(range.interval.min).min(range.interval.max),
+            None => range.interval.min,
+        };
+        let closest = Descriptor {
+            width,
+            height,
+            pixfmt: range.pixfmt.clone(),
+            interval,
+        };
+
+        let mut descs = range.descriptors();
+        if !descs.contains(&closest) {
+            descs.push(closest);
+        }
+        descs
+    }
+
+    /// Ranks candidates according to the constraints
+    ///
+    /// # Arguments
+    ///
+    /// * `candidates` - Streams to choose from
+    pub fn select<I: IntoIterator<Item = Candidate>>(&self, candidates: I) -> Selection {
+        let mut accepted = Vec::new();
+        let mut rejected = Vec::new();
+
+        for candidate in candidates {
+            let reasons = self.check(&candidate);
+            if reasons.is_empty() {
+                accepted.push(candidate);
+            } else {
+                rejected.push(Rejection {
+                    desc: candidate.desc,
+                    reasons,
+                });
+            }
+        }
+
+        // stable sort, so equal candidates stay in the order reported by the device
+        accepted.sort_by_key(|candidate| self.score(candidate));
+
+        Selection { accepted, rejected }
+    }
+
+    /// Returns all hard constraints violated by a candidate
+    fn check(&self, candidate: &Candidate) -> Vec<Reason> {
+        let desc = &candidate.desc;
+        let mut reasons = Vec::new();
+
+        if !candidate.native && !self.conversion {
+            reasons.push(Reason::Conversion);
+        }
+
+        if !self.required_pixfmts.is_empty() && !self.required_pixfmts.contains(&desc.pixfmt) {
+            reasons.push(Reason::PixelFormat);
+        }
+
+        if let Some((width, height)) = self.min_resolution {
+            if desc.width < width || desc.height < height {
+                reasons.push(Reason::Resolution { width, height });
+            }
+        }
+
+        if let Some((width, height)) = self.aspect_ratio {
+            // compare desc.width / desc.height against width / height without rounding
+            let lhs = desc.width as u64 * height as u64;
+            let rhs = desc.height as u64 * width as u64;
+            if lhs.abs_diff(rhs) * 100 > rhs * ASPECT_RATIO_TOLERANCE {
+                reasons.push(Reason::AspectRatio { width, height });
+            }
+        }
+
+        reasons
+    }
+
+    /// Returns a sort key, smaller keys denote better candidates
+    #[allow(clippy::type_complexity)]
+    fn score(&self, candidate: &Candidate) -> (u64, Reverse<u64>, u64, Reverse<u64>, usize, bool) {
+        let desc = &candidate.desc;
+
+        let area = desc.width as u64 * desc.height as u64;
+        let size_distance = match self.resolution {
+            Some((width, height)) => {
+                desc.width.abs_diff(width) as u64 + desc.height.abs_diff(height) as u64
+            }
+            None => 0,
+        };
+
+        // frame rates in 1/1000 fps, which is plenty to tell common rates apart
+        let millifps = |interval: Interval| (interval.fps() * 1000.0).round() as u64;
+        let rate = millifps(desc.interval);
+        let rate_distance = match self.interval {
+            Some(interval) => rate.abs_diff(millifps(interval)),
+            None => 0,
+        };
+
+        let pixfmt_rank = self
+            .pixfmts
+            .iter()
+            .position(|pixfmt| *pixfmt == desc.pixfmt)
+            .unwrap_or(self.pixfmts.len());
+
+        (
+            size_distance,
+            Reverse(area),
+            rate_distance,
+            Reverse(rate),
+            pixfmt_rank,
+            !candidate.native,
+        )
+    }
+}
+
+/// Returns the value described by the bounds which is closest to the given one
+fn nearest(bounds: &Bounds<u32>, value: u32) -> u32 {
+    let value = value.max(bounds.min).min(bounds.max);
+    if bounds.step == 0 {
+        return bounds.min;
+    }
+
+    // round to the nearest step, but stay within the bounds
+    let steps = (value - bounds.min + bounds.step / 2) / bounds.step;
+    let value = bounds.min as u64 + steps as u64 * bounds.step as u64;
+    if value > bounds.max as u64 {
+        bounds.max - (bounds.max - bounds.min) % bounds.step
+    } else {
+        value as u32
+    }
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Stream considered for selection
+pub struct Candidate {
+    /// Stream descriptor
+    pub desc: Descriptor,
+    /// Whether the device provides the stream without any conversion
+    pub native: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+/// Hard constraint violated by a candidate
+pub enum Reason {
+    /// Stream is only available through conversion
+    Conversion,
+    /// Pixel format is not among the required ones
+    PixelFormat,
+    /// Frame size is below the minimum resolution
+    Resolution { width: u32, height: u32 },
+    /// Frame size does not match the aspect ratio
+    AspectRatio { width: u32, height: u32 },
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::Conversion => write!(f, "requires conversion"),
+            Reason::PixelFormat => write!(f, "pixel format is not acceptable"),
+            Reason::Resolution { width, height } => {
+                write!(
+                    f,
+                    "smaller than the minimum resolution of {}x{}",
+                    width, height
+                )
+            }
+            Reason::AspectRatio { width, height } => {
+                write!(f, "aspect ratio differs from {}:{}", width, height)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Candidate which was rejected
+pub struct Rejection {
+    /// Stream descriptor
+    pub desc: Descriptor,
+    /// All constraints violated by the stream
+    pub reasons: Vec<Reason>,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}x{} {} @ {}s: ",
+            self.desc.width, self.desc.height, self.desc.pixfmt, self.desc.interval
+        )?;
+        for (i, reason) in self.reasons.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Outcome of a stream selection
+pub struct Selection {
+    /// Candidates satisfying all constraints, best first
+    pub accepted: Vec<Candidate>,
+    /// Candidates violating at least one constraint
+    pub rejected: Vec<Rejection>,
+}
+
+impl Selection {
+    /// Returns the best stream, if any satisfies the constraints
+    pub fn best(&self) -> Option<&Descriptor> {
+        self.accepted.first().map(|candidate| &candidate.desc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(width: u32, height: u32, pixfmt: PixelFormat, fps: u32) -> Candidate {
+        Candidate {
+            desc: Descriptor {
+                width,
+                height,
+                pixfmt,
+                interval: Interval::from_fps(fps),
+            },
+            native: true,
+        }
+    }
+
+    fn best(constraints: &Constraints, candidates: &[Candidate]) -> Descriptor {
+        let selection = constraints.select(candidates.iter().cloned());
+        selection.best().unwrap().clone()
+    }
+
+    #[test]
+    fn resolution_before_rate() {
+        let candidates = [
+            candidate(640, 480, PixelFormat::Yuyv(16), 60),
+            candidate(1280, 720, PixelFormat::Yuyv(16), 10),
+            candidate(1280, 720, PixelFormat::Yuyv(16), 30),
+            candidate(1920, 1080, PixelFormat::Yuyv(16), 30),
+        ];
+
+        // without preferences, the largest and fastest stream wins
+        let desc = best(&Constraints::new(), &candidates);
+        assert_eq!((desc.width, desc.interval), (1920, Interval::from_fps(30)));
+
+        let desc = best(
+            &Constraints::new().resolution(1280, 720).fps(25),
+            &candidates,
+        );
+        assert_eq!((desc.width, desc.interval), (1280, Interval::from_fps(30)));
+
+        let desc = best(
+            &Constraints::new().resolution(1280, 720).fps(5),
+            &candidates,
+        );
+        assert_eq!((desc.width, desc.interval), (1280, Interval::from_fps(10)));
+    }
+
+    #[test]
+    fn pixfmt_preference() {
+        let candidates = [
+            candidate(640, 480, PixelFormat::Yuyv(16), 30),
+            candidate(640, 480, PixelFormat::Jpeg, 30),
+            candidate(640, 480, PixelFormat::Rgb(24), 30),
+        ];
+
+        // preferences only decide between otherwise equal streams
+        let constraints = Constraints::new()
+            .pixfmt(PixelFormat::Jpeg)
+            .pixfmt(PixelFormat::Rgb(24));
+        assert_eq!(best(&constraints, &candidates).pixfmt, PixelFormat::Jpeg);
+        let selection = constraints.select(candidates.iter().cloned());
+        assert!(selection.rejected.is_empty());
+        assert_eq!(selection.accepted[2].desc.pixfmt, PixelFormat::Yuyv(16));
+
+        // preferences do not outweigh the resolution
+        let mut larger = candidates.to_vec();
+        larger.push(candidate(1280, 720, PixelFormat::Yuyv(16), 30));
+        assert_eq!(best(&constraints, &larger).pixfmt, PixelFormat::Yuyv(16));
+
+        // required formats reject all others
+        let constraints = Constraints::new().require_pixfmt(PixelFormat::Rgb(24));
+        let selection = constraints.select(larger.iter().cloned());
+        assert_eq!(selection.accepted.len(), 1);
+        assert_eq!(selection.best().unwrap().pixfmt, PixelFormat::Rgb(24));
+        assert!(selection
+            .rejected
+            .iter()
+            .all(|rejection| rejection.reasons == [Reason::PixelFormat]));
+    }
+
+    #[test]
+    fn hard_constraints() {
+        let mut converted = candidate(1920, 1080, PixelFormat::Rgb(24), 30);
+        converted.native = false;
+        let candidates = [
+            candidate(640, 480, PixelFormat::Yuyv(16), 30),
+            candidate(854, 480, PixelFormat::Yuyv(16), 30),
+            converted,
+        ];
+
+        let constraints = Constraints::new().aspect_ratio(16, 9).conversion(false);
+        let selection = constraints.select(candidates.iter().cloned());
+        assert_eq!(selection.best().unwrap().width, 854);
+        assert_eq!(selection.rejected.len(), 2);
+
+        let constraints = Constraints::new().min_resolution(800, 600);
+        let selection = constraints.select(candidates.iter().cloned());
+        assert_eq!(selection.best().unwrap().width, 1920);
+        // native streams win on ties
+        let mut native = candidates[2].clone();
+        native.native = true;
+        let selection = constraints.select(vec![candidates[2].clone(), native]);
+        assert!(selection.accepted[0].native);
+    }
+
+    #[test]
+    fn range_candidates() {
+        let range = Range {
+            pixfmt: PixelFormat::Yuyv(16),
+            width: Bounds {
+                min: 16,
+                max: 4096,
+                step: 16,
+            },
+            height: Bounds {
+                min: 16,
+                max: 2160,
+                step: 2,
+            },
+            interval: Bounds {
+                min: Interval::from_fps(60),
+                max: Interval::from_fps(1),
+                step: Interval::new(1, 1),
+            },
+        };
+
+        let constraints = Constraints::new().resolution(1000, 555).fps(45);
+        let candidates: Vec<Candidate> = constraints
+            .candidates(&range)
+            .into_iter()
+            .map(|desc| Candidate { desc, native: true })
+            .collect();
+        assert!(candidates
+            .iter()
+            .all(|candidate| range.contains(&candidate.desc)));
+
+        let desc = best(&constraints, &candidates);
+        assert_eq!((desc.width, desc.height), (1008, 556));
+        assert_eq!(desc.interval, Interval::from_fps(45));
+
+        // preferences beyond the bounds are clamped
+        let constraints = Constraints::new().resolution(8000, 8000).fps(120);
+        let descs = constraints.candidates(&range);
+        let closest = descs.last().unwrap();
+        assert_eq!((closest.width, closest.height), (4096, 2160));
+        assert_eq!(closest.interval, Interval::from_fps(60));
+    }
+
+    #[test]
+    fn nearest_value() {
+        let bounds = Bounds {
+            min: 10,
+            max: 95,
+            step: 10,
+        };
+        assert_eq!(nearest(&bounds, 0), 10);
+        assert_eq!(nearest(&bounds, 24), 20);
+        assert_eq!(nearest(&bounds, 25), 30);
+        assert_eq!(nearest(&bounds, 94), 90);
+        assert_eq!(nearest(&bounds, 1000), 90);
+        assert_eq!(nearest(&Bounds::fixed(42), 7), 42);
+    }
+}